@@ -0,0 +1,253 @@
+//! The `lalrpop!` macro, which generates a parser from a grammar
+//! written inline in Rust code, rather than from a `.lalrpop` file
+//! processed by a build script. For example,
+//!
+//! ```ignore
+//! #[macro_use] extern crate lalrpop_macro;
+//! extern crate lalrpop_util;
+//!
+//! mod num {
+//!     lalrpop! {
+//!         use std::str::FromStr;
+//!
+//!         grammar;
+//!
+//!         pub Num: i32 = r"[0-9]+" => i32::from_str(<>).unwrap();
+//!     }
+//! }
+//! ```
+//!
+//! defines `num::parse_Num`. The macro expands to the same items as
+//! the file LALRPOP would generate for the grammar, so it is best
+//! given a module of its own, and the crate using it needs
+//! `lalrpop-util` too. Errors in the grammar are reported as compile
+//! errors pointing at the offending tokens.
+//!
+//! The grammar goes through the Rust tokenizer first, so comments
+//! (doc comments included) are dropped and everything has to be a
+//! valid Rust token: regular expressions are best written as raw
+//! strings, and grammars cannot `import` other files. Spaces between
+//! tokens are lost as well, so an identifier followed by `<` is read as
+//! a use of a macro only if a macro of that name is defined, as in
+//! `Comma<T>` above, wherever the spaces were.
+
+extern crate lalrpop;
+extern crate proc_macro;
+
+use lalrpop::{Configuration, Error};
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::collections::HashSet;
+use std::iter::FromIterator;
+
+#[cfg(test)]
+mod test;
+
+#[proc_macro]
+pub fn lalrpop(input: TokenStream) -> TokenStream {
+    let mut source = Source::new();
+    let macros = macro_names(input.clone());
+    append(&mut source, &macros, &mut false, input);
+    match Configuration::new().process_str(&source.text) {
+        Ok(code) => {
+            code.parse().unwrap_or_else(|err| {
+                let message = format!("generated code does not tokenize: {}", err);
+                compile_error(&message, Span::call_site(), Span::call_site())
+            })
+        }
+        Err(Error::Grammar(diagnostics)) => {
+            let errors = diagnostics.iter().map(|diagnostic| {
+                let (start, end) =
+                    source.spans(diagnostic.span).unwrap_or((Span::call_site(), Span::call_site()));
+                compile_error(&diagnostic.message, start, end)
+            });
+            TokenStream::from_iter(errors)
+        }
+        Err(Error::Io(err)) => {
+            compile_error(&err.to_string(), Span::call_site(), Span::call_site())
+        }
+    }
+}
+
+/// Appends the tokens of `input` to `source`. `joined` tells whether
+/// the next token was written right after the one before it, in which
+/// case they are not separated in the text either, since LALRPOP reads
+/// `=>` differently from `= >`, and `Comma<T>` differently from
+/// `Comma <T>`.
+fn append(source: &mut Source<Span>,
+          macros: &HashSet<String>,
+          joined: &mut bool,
+          input: TokenStream) {
+    let trees: Vec<TokenTree> = input.into_iter().collect();
+    let mut index = 0;
+    while index < trees.len() {
+        let doc_len = doc_attribute(&trees[index..]);
+        if doc_len > 0 {
+            index += doc_len;
+            continue;
+        }
+        match trees[index] {
+            TokenTree::Group(ref group) => {
+                let (open, close) = match group.delimiter() {
+                    Delimiter::Parenthesis => ("(", ")"),
+                    Delimiter::Brace => ("{", "}"),
+                    Delimiter::Bracket => ("[", "]"),
+                    Delimiter::None => ("", ""),
+                };
+                source.push(open, group.span_open(), *joined);
+                *joined = false;
+                append(source, macros, joined, group.stream());
+                source.push(close, group.span_close(), false);
+                *joined = false;
+            }
+            ref tree => {
+                source.push(&tree.to_string(), tree.span(), *joined);
+                *joined = joins(tree, trees.get(index + 1), macros);
+            }
+        }
+        index += 1;
+    }
+}
+
+/// Whether `tree` was written right before `next`, as far as LALRPOP
+/// cares. Punctuation says so itself: it is `Joint` when another one
+/// follows, as in `=>`, or when it starts a lifetime. Besides that,
+/// only `@L` and `@R`, and the name of a macro followed by its
+/// arguments, have to be kept together.
+fn joins(tree: &TokenTree, next: Option<&TokenTree>, macros: &HashSet<String>) -> bool {
+    match (tree, next) {
+        (&TokenTree::Punct(ref punct), _) if punct.spacing() == Spacing::Joint => true,
+        (&TokenTree::Punct(ref punct), Some(&TokenTree::Ident(ref ident))) => {
+            punct.as_char() == '@' && (ident.to_string() == "L" || ident.to_string() == "R")
+        }
+        (&TokenTree::Ident(ref ident), Some(&TokenTree::Punct(ref punct))) => {
+            punct.as_char() == '<' && macros.contains(&ident.to_string())
+        }
+        _ => false,
+    }
+}
+
+/// The names of the macros defined in the grammar `input`: the
+/// identifiers followed by `<` at the start of an item, which is where
+/// the grammar, after any annotations and `pub`, begins and where each
+/// `;` or `{ .. }` at the top level ends one.
+fn macro_names(input: TokenStream) -> HashSet<String> {
+    let trees: Vec<TokenTree> = input.into_iter().collect();
+    let mut names = HashSet::new();
+    let mut item_start = true;
+    for (index, tree) in trees.iter().enumerate() {
+        item_start = match *tree {
+            TokenTree::Ident(ref ident) => {
+                let name = ident.to_string();
+                if item_start {
+                    if let Some(&TokenTree::Punct(ref punct)) = trees.get(index + 1) {
+                        if punct.as_char() == '<' {
+                            names.insert(name.clone());
+                        }
+                    }
+                }
+                item_start && name == "pub"
+            }
+            TokenTree::Punct(ref punct) => {
+                punct.as_char() == ';' || (item_start && (punct.as_char() == '#' ||
+                                                          punct.as_char() == '!'))
+            }
+            TokenTree::Group(ref group) => {
+                group.delimiter() == Delimiter::Brace ||
+                (item_start && group.delimiter() == Delimiter::Bracket)
+            }
+            TokenTree::Literal(_) => false,
+        };
+    }
+    names
+}
+
+/// The number of tokens in the doc attribute that `trees` starts with,
+/// or 0 if it does not start with one. Doc comments arrive as
+/// `#[doc = ".."]` or `#![doc = ".."]`, which LALRPOP would take for
+/// an annotation.
+fn doc_attribute(trees: &[TokenTree]) -> usize {
+    let is_punct = |tree: Option<&TokenTree>, c: char| {
+        match tree {
+            Some(&TokenTree::Punct(ref punct)) => punct.as_char() == c,
+            _ => false,
+        }
+    };
+    if !is_punct(trees.get(0), '#') {
+        return 0;
+    }
+    let len = if is_punct(trees.get(1), '!') { 3 } else { 2 };
+    match trees.get(len - 1) {
+        Some(&TokenTree::Group(ref group)) if group.delimiter() == Delimiter::Bracket => {
+            match group.stream().into_iter().next() {
+                Some(TokenTree::Ident(ref ident)) if ident.to_string() == "doc" => len,
+                _ => 0,
+            }
+        }
+        _ => 0,
+    }
+}
+
+/// The grammar text given to LALRPOP, which is the input of the macro
+/// printed back out, along with the span `S` of each token in it.
+struct Source<S> {
+    text: String,
+
+    /// The byte offsets in `text` of each token, and its span in the
+    /// input of the macro.
+    tokens: Vec<(usize, usize, S)>,
+}
+
+impl<S: Copy> Source<S> {
+    fn new() -> Source<S> {
+        Source { text: String::new(), tokens: vec![] }
+    }
+
+    /// Appends `token`, separated from the one before it by a space
+    /// unless it is `joined` to it. Empty tokens are skipped.
+    fn push(&mut self, token: &str, span: S, joined: bool) {
+        if token.is_empty() {
+            return;
+        }
+        if !self.text.is_empty() && !joined {
+            self.text.push(' ');
+        }
+        let start = self.text.len();
+        self.text.push_str(token);
+        self.tokens.push((start, self.text.len(), span));
+    }
+
+    /// The spans of the first and last tokens that `span` covers. If
+    /// it is empty, as when it points at the end of the grammar, the
+    /// token that follows it is used instead, or failing that the one
+    /// before it. `None` if there are no tokens at all.
+    fn spans(&self, span: lalrpop::Span) -> Option<(S, S)> {
+        let covered: Vec<S> =
+            self.tokens.iter()
+                       .filter(|&&(start, end, _)| start < span.1 && span.0 < end)
+                       .map(|&(_, _, span)| span)
+                       .collect();
+        if let (Some(&first), Some(&last)) = (covered.first(), covered.last()) {
+            return Some((first, last));
+        }
+        self.tokens.iter()
+                   .find(|&&(start, _, _)| start >= span.0)
+                   .or(self.tokens.last())
+                   .map(|&(_, _, span)| (span, span))
+    }
+}
+
+/// `compile_error!("message")`, spanning from `start` to `end`: the
+/// compiler reports errors from a macro at the span of its name
+/// through that of its arguments.
+fn compile_error(message: &str, start: Span, end: Span) -> TokenStream {
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(start);
+    let mut message = Literal::string(message);
+    message.set_span(end);
+    let mut arguments = Group::new(Delimiter::Brace,
+                                   TokenStream::from(TokenTree::Literal(message)));
+    arguments.set_span(end);
+    TokenStream::from_iter(vec![TokenTree::Ident(Ident::new("compile_error", start)),
+                                TokenTree::Punct(bang),
+                                TokenTree::Group(arguments)])
+}
@@ -0,0 +1,41 @@
+use lalrpop::Span;
+use super::Source;
+
+/// The source text of `tokens`, each given with whether it is joined
+/// to the one before it, and spanned by its index.
+fn source(tokens: &[(&str, bool)]) -> Source<usize> {
+    let mut source = Source::new();
+    for (index, &(token, joined)) in tokens.iter().enumerate() {
+        source.push(token, index, joined);
+    }
+    source
+}
+
+#[test]
+fn joined_tokens() {
+    let source = source(&[("X", false), ("=", false), ("Comma", false), ("<", true),
+                          ("\"a\"", true), (">", true), ("=", false), (">", true)]);
+    assert_eq!(source.text, r#"X = Comma<"a"> =>"#);
+}
+
+#[test]
+fn spans_of_covered_tokens() {
+    // X = Comma<"a">
+    let source = source(&[("X", false), ("=", false), ("Comma", false), ("<", true),
+                          ("\"a\"", true), (">", true)]);
+    assert_eq!(source.spans(Span(0, 1)), Some((0, 0)));
+    assert_eq!(source.spans(Span(4, 14)), Some((2, 5)));
+    // a span within a single token
+    assert_eq!(source.spans(Span(10, 12)), Some((4, 4)));
+}
+
+#[test]
+fn spans_of_empty_span() {
+    // X = ;
+    let source = source(&[("X", false), ("=", false), (";", false)]);
+    // between `=` and `;`, the token that follows is used
+    assert_eq!(source.spans(Span(3, 3)), Some((2, 2)));
+    // at the end, the last token is used
+    assert_eq!(source.spans(Span(5, 5)), Some((2, 2)));
+    assert_eq!(Source::<usize>::new().spans(Span(0, 0)), None);
+}
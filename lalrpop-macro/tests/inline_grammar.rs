@@ -0,0 +1,73 @@
+#[macro_use]
+extern crate lalrpop_macro;
+extern crate lalrpop_util;
+
+mod inline_grammar {
+    lalrpop! {
+        use std::str::FromStr;
+        use lalrpop_util::ParseError;
+
+        grammar;
+
+        /// doc comments are dropped, rather than read as annotations
+        pub Sum: i32 = {
+            <l:Sum> "+" <r:Num> => l + r,
+            Num,
+        };
+
+        pub Nums = Comma<Num>;
+
+        Comma<T>: Vec<T> = {
+            <v:(<T> ",")*> <e:T?> => v.into_iter().chain(e).collect(),
+        };
+
+        // `Num` is not a macro, so `<Num>` is a selected symbol
+        pub Second: i32 = Num <Num>;
+
+        pub Spanned: (usize, usize) = <l:@L> Num <r:@R> => (l, r);
+
+        pub Digit: i32 = <n:Num> =>? {
+            if n < 10 {
+                Ok(n)
+            } else {
+                Err(ParseError::User { error: () })
+            }
+        };
+
+        pub Name: &'static str = r"[a-z]+" => "name";
+
+        Num: i32 = r"[0-9]+" => i32::from_str(<>).unwrap();
+    }
+}
+
+#[test]
+fn inline_grammar() {
+    assert_eq!(inline_grammar::parse_Sum("1 + 2 + 3").unwrap(), 6);
+    assert!(inline_grammar::parse_Sum("1 +").is_err());
+}
+
+#[test]
+fn inline_macro() {
+    assert_eq!(inline_grammar::parse_Nums("1, 22, 3").unwrap(), vec![1, 22, 3]);
+}
+
+#[test]
+fn inline_selection_after_identifier() {
+    assert_eq!(inline_grammar::parse_Second("1 2").unwrap(), 2);
+}
+
+#[test]
+fn inline_locations() {
+    assert_eq!(inline_grammar::parse_Spanned(" 12 ").unwrap(), (1, 3));
+}
+
+#[test]
+fn inline_fallible_action() {
+    assert_eq!(inline_grammar::parse_Digit("7").unwrap(), 7);
+    assert!(inline_grammar::parse_Digit("17").is_err());
+}
+
+#[test]
+fn inline_lifetime() {
+    assert_eq!(inline_grammar::parse_Name("abc").unwrap(), "name");
+}
@@ -297,6 +297,7 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         self.write_parse_mod(|this| {
             try!(this.write_value_type_defn());
             try!(this.write_parse_table());
+            try!(this.write_expected_tokens_fn());
             try!(this.write_parser_fn());
             try!(this.emit_reduce_actions());
             try!(this.emit_downcast_fns());
@@ -386,6 +387,35 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         Ok(())
     }
 
+    fn write_expected_tokens_fn(&mut self) -> io::Result<()> {
+        rust!(self.out,
+              "fn {}expected_tokens({}state: usize) -> Vec<::std::string::String> {{",
+              self.prefix,
+              self.prefix);
+
+        // The terminals for which the state has an action, in the
+        // order of the columns of `ACTION`.
+        rust!(self.out, "const {}TERMINALS: &'static [&'static str] = &[", self.prefix);
+        for terminal in &self.grammar.terminals.all {
+            rust!(self.out, "r###\"{}\"###,", terminal);
+        }
+        rust!(self.out, "];");
+        rust!(self.out,
+              "{}ACTION[({}state * {})..].iter().zip({}TERMINALS).filter_map(|(&action, terminal)| {{",
+              self.prefix,
+              self.prefix,
+              self.grammar.terminals.all.len(),
+              self.prefix);
+        rust!(self.out, "if action == 0 || *terminal == \"error\" {{");
+        rust!(self.out, "None");
+        rust!(self.out, "}} else {{");
+        rust!(self.out, "Some(terminal.to_string())");
+        rust!(self.out, "}}");
+        rust!(self.out, "}}).collect()");
+        rust!(self.out, "}}");
+        Ok(())
+    }
+
     fn write_reduction<'s>(custom: &TableDriven<'grammar>, state: &'s LR1State, token: Token) -> (i32, Comment<'s, Token>) {
         let reduction = state.reductions
                              .iter()
@@ -504,7 +534,7 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
             self.prefix,
             self.prefix);
         rust!(self.out, "token: Some({}lookahead.clone()),", self.prefix);
-        rust!(self.out, "expected: vec![],");
+        rust!(self.out, "expected: {}expected_tokens({}state),", self.prefix, self.prefix);
         rust!(self.out, "}};");
         rust!(self.out, "let mut {}dropped_tokens = Vec::new();", self.prefix);
         let lookahead_start = format!("Some(&{}lookahead.0)", self.prefix);
@@ -542,6 +572,7 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
               self.prefix);
         rust!(self.out, "error: {}error,", self.prefix);
         rust!(self.out, "dropped_tokens: {}dropped_tokens,", self.prefix);
+        rust!(self.out, "repair: None,");
         rust!(self.out, "}};");
         rust!(self.out,
             "{}symbols.push(({}start, {}Symbol::Termerror({}recovery), {}end));",
@@ -621,7 +652,7 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
               self.prefix,
               self.prefix);
         rust!(self.out, "token: None,");
-        rust!(self.out, "expected: vec![],");
+        rust!(self.out, "expected: {}expected_tokens({}state),", self.prefix, self.prefix);
         rust!(self.out, "}};");
 
         let extra_test = format!("&& {}EOF_ACTION[({}error_state as usize - 1)] != 0 ",
@@ -644,6 +675,7 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
               self.prefix);
         rust!(self.out, "error: {}error,", self.prefix);
         rust!(self.out, "dropped_tokens: Vec::new(),");
+        rust!(self.out, "repair: None,");
         rust!(self.out, "}};");
         rust!(self.out,
             "{}symbols.push(({}last_location.clone(), {}Symbol::Termerror({}recovery), {}last_location.clone()));",
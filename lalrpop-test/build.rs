@@ -1,10 +1,25 @@
 extern crate lalrpop;
 
+use std::env;
+
 fn main() {
+    // several jobs, so that the tests also cover parsers generated in
+    // parallel
     lalrpop::Configuration::new()
         .emit_comments(true)
         .force_build(true)
         .unit_test()
-        .process_current_dir()
+        .set_jobs(4)
+        .process_dir("src")
+        .unwrap();
+
+    // `lalrpop_mod!` finds its parsers in `OUT_DIR`; the grammars for
+    // it are kept out of `src`, so that they are not also built there
+    lalrpop::Configuration::new()
+        .force_build(true)
+        .unit_test()
+        .set_in_dir("grammars")
+        .set_out_dir(env::var("OUT_DIR").unwrap())
+        .process_dir("grammars")
         .unwrap();
 }
@@ -1,9 +1,11 @@
 extern crate diff;
+#[macro_use]
 extern crate lalrpop_util;
 
 use std::cell::RefCell;
+use std::fmt::Debug;
 
-use lalrpop_util::{ErrorRecovery, ParseError};
+use lalrpop_util::{ErrorRecovery, ParseError, Repair};
 
 use util::tok::Tok;
 
@@ -24,6 +26,14 @@ mod expr_lalr;
 /// more interesting demonstration of parsing full expressions, using intern tok
 mod expr_intern_tok;
 
+/// a grammar that is LR(1) but not LALR(1), plus precedence
+/// annotations, built with the lane table algorithm
+mod lane_table;
+
+/// expressions written as a single nonterminal, disambiguated with
+/// precedence and associativity annotations
+mod expr_prec;
+
 /// test that passes in lifetime/type/formal parameters and threads
 /// them through, building an AST from the result
 mod expr_arena;
@@ -43,6 +53,21 @@ mod inline;
 /// test that exercises internal token generation, as well as locations and spans
 mod intern_tok;
 
+/// test of a `skip` declaration, where comments are skipped but
+/// newlines are significant
+mod skip;
+
+/// test of a `match` declaration, giving a keyword priority over
+/// identifiers and renaming terminals
+mod match_tok;
+
+/// test of an `import` declaration, reusing the nonterminals and
+/// macros of `import_expr`
+mod import;
+
+/// grammar imported by the import test
+mod import_expr;
+
 /// test that exercises using a lifetime parameter in the token type
 mod lifetime_tok;
 
@@ -66,6 +91,15 @@ mod error;
 mod error_recovery;
 mod error_recovery_pull_182;
 
+/// test error recovery in a recursive ascent parser
+mod error_recovery_ascent;
+
+/// test the `repair` error recovery strategy
+mod error_recovery_repair;
+
+/// test capping the number of error recoveries
+mod error_recovery_limit;
+
 /// test for inlining expansion issue #55
 mod issue_55;
 
@@ -78,6 +112,11 @@ mod no_clone_tok;
 
 mod util;
 
+lalrpop_mod!(
+    /// test for a parser generated into `OUT_DIR`, from
+    /// `grammars/sum.lalrpop`
+    out_dir_sum, "/sum.rs");
+
 /// This constant is here so that some of the generator parsers can
 /// refer to it in order to test `super::` handling in action code.
 const ZERO: i32 = 0;
@@ -107,6 +146,64 @@ fn expr_test5() {
     util::test(|v| expr::parse_Expr(11, v), "22 * 3 - 6", 22*11 * 3*11 - 6*11);
 }
 
+#[test]
+fn expr_prec_test1() {
+    util::test(|v| expr_prec::parse_Expr(1, v), "22 - 3 - 5", 22 - 3 - 5);
+}
+
+#[test]
+fn expr_prec_test2() {
+    util::test(|v| expr_prec::parse_Expr(1, v), "22 - 3 * 5 + 4 / 2", 22 - 3 * 5 + 4 / 2);
+}
+
+#[test]
+fn expr_prec_test3() {
+    util::test(|v| expr_prec::parse_Expr(1, v), "(22 - 3) * 5", (22 - 3) * 5);
+}
+
+#[test]
+fn skip_comments() {
+    assert_eq!(skip::parse_Lines("1 + 2 // three\n/* four\n */ 4\n").unwrap(),
+               vec![3, 4]);
+}
+
+#[test]
+fn skip_newline_significant() {
+    match skip::parse_Lines("1 +\n 2\n") {
+        //                   012 3
+        Err(ParseError::UnrecognizedToken { token: Some((3, _, 4)), .. }) => { }
+        r => {
+            panic!("invalid result {:?}", r);
+        }
+    }
+}
+
+#[test]
+fn match_tok_keyword() {
+    assert_eq!(match_tok::parse_Stmt("LET x = 5;").unwrap(), ("x", 5));
+    assert_eq!(match_tok::parse_Stmt("let lettuce = 1;").unwrap(), ("lettuce", 1));
+}
+
+#[test]
+fn match_tok_keyword_as_id() {
+    match match_tok::parse_Stmt("let let = 1;") {
+        //                       01234567
+        Err(ParseError::UnrecognizedToken { token: Some((4, _, 7)), .. }) => { }
+        r => {
+            panic!("invalid result {:?}", r);
+        }
+    }
+}
+
+#[test]
+fn import_nonterminals_and_macros() {
+    assert_eq!(import::parse_Exprs("[1 + 2, (3 - 1) * 2, 5]").unwrap(),
+               vec![3, 4, 5]);
+    assert_eq!(import::parse_Count("#22").unwrap(), 22);
+    assert_eq!(import::parse_Expr("2 * 3").unwrap(),
+               import_expr::parse_Expr("2 * 3").unwrap());
+}
+
 #[test]
 fn expr_intern_tok_test1() {
     assert_eq!(expr_intern_tok::parse_Expr(1, "22 - 3").unwrap(), 22 - 3);
@@ -163,6 +260,20 @@ fn expr_lalr_test5() {
     util::test(|v| expr_lalr::parse_Expr(11, v), "22 * 3 - 6", 22*11 * 3*11 - 6*11);
 }
 
+#[test]
+fn lane_table_not_lalr() {
+    assert_eq!(lane_table::parse_Pair("a e d").unwrap(), "aXd");
+    assert_eq!(lane_table::parse_Pair("a e c").unwrap(), "aYc");
+    assert_eq!(lane_table::parse_Pair("b e c").unwrap(), "bXc");
+    assert_eq!(lane_table::parse_Pair("b e d").unwrap(), "bYd");
+    assert!(lane_table::parse_Pair("b e d d").is_err());
+}
+
+#[test]
+fn lane_table_precedence() {
+    assert_eq!(lane_table::parse_Expr("22 - 3 * 5 - 4").unwrap(), 22 - 3 * 5 - 4);
+}
+
 #[test]
 fn inline_test1() {
     assert_eq!(inline::parse_E("& L L").unwrap(), "& L L");
@@ -193,6 +304,24 @@ fn sub_table_test1() {
     util::test(sub_table::parse_S, "22 - 3", 22 - 3);
 }
 
+#[test]
+fn sub_ascent_expected_tokens() {
+    // recursive ascent and table-driven parsers report the same
+    // expected tokens
+    for input in &["22 -", "22 - )", "(22"] {
+        let tokens = || util::tok::tokenize(input).into_iter().map(|(_, tok, _)| tok);
+        let ascent = sub_ascent::parse_S(tokens());
+        let table = sub_table::parse_S(tokens());
+        assert_eq!(ascent, table);
+        match ascent {
+            Err(ParseError::UnrecognizedToken { ref expected, .. }) => {
+                assert!(!expected.is_empty());
+            }
+            r => panic!("unexpected response from parser: {:?}", r),
+        }
+    }
+}
+
 #[test]
 fn expr_arena_test1() {
     use expr_arena_ast::*;
@@ -281,6 +410,59 @@ fn use_super_test1() {
     util::test(|v| use_super::parse_S(v), "()", 0);
 }
 
+#[test]
+fn expr_metadata() {
+    assert_eq!(expr::__metadata::TERMINALS,
+               &[r#""(""#, r#"")""#, r#""*""#, r#""+""#, r#""-""#, r#""/""#, "Fraction", "Num"]);
+
+    let start = expr::__metadata::START_SYMBOLS[0];
+    assert_eq!((start.name, start.ty), ("Expr", "i32"));
+    let expected: Vec<_> = start.expected.iter().map(|&t| t.name()).collect();
+    assert_eq!(expected, vec![r#""(""#, "Num"]);
+}
+
+#[test]
+fn expr_push_parser() {
+    let mut parser = expr::ExprParser::new(1);
+    for (_, token, _) in util::tok::tokenize("22 - (3 + 5)") {
+        parser.push(token).unwrap();
+    }
+    assert_eq!(parser.finish(), Ok(22 - (3 + 5)));
+}
+
+#[test]
+fn expr_push_parser_error() {
+    let mut parser = expr::ExprParser::new(1);
+    parser.push(Tok::Num(22)).unwrap();
+    assert!(parser.push(Tok::Num(3)).is_err());
+}
+
+#[test]
+fn error_recovery_push_parser() {
+    let errors = RefCell::new(vec![]);
+    let mut parser = error_recovery::ItemParser::new(&errors);
+    for (_, token, _) in util::tok::tokenize("(+++)") {
+        parser.push(token).unwrap();
+    }
+    assert_eq!(parser.finish(), Ok("()".to_string()));
+    assert_eq!(errors.borrow()[0].dropped_tokens,
+               vec![((), Tok::Plus, ()), ((), Tok::Plus, ())]);
+}
+
+#[test]
+fn error_recovery_push_parser_eof_while_dropping() {
+    let errors = RefCell::new(vec![]);
+    let mut parser = error_recovery::ItemParser::new(&errors);
+    for (_, token, _) in util::tok::tokenize("(++") {
+        parser.push(token).unwrap();
+    }
+    assert_eq!(parser.finish(), Err(ParseError::UnrecognizedToken {
+        token: Some(((), Tok::Plus, ())),
+        expected: vec!["\")\"".to_string()],
+    }));
+    assert!(errors.borrow().is_empty());
+}
+
 #[test]
 fn error_test1() {
     match util::test_err_gen(error::parse_Items, "---+") {
@@ -303,6 +485,7 @@ fn error_recovery_eof() {
             expected: vec!["\"-\"".to_string()],
         },
         dropped_tokens: vec![],
+        repair: None,
     });
 }
 
@@ -329,6 +512,7 @@ fn error_recovery_extra_token() {
             expected: vec!["\")\"".to_string()],
         },
         dropped_tokens: vec![((), Tok::Plus, ())],
+        repair: None,
     });
 }
 
@@ -344,6 +528,7 @@ fn error_recovery_dont_drop_unrecognized_token() {
             expected: vec!["\"-\"".to_string()],
         },
         dropped_tokens: vec![],
+        repair: None,
     });
 }
 
@@ -359,6 +544,7 @@ fn error_recovery_multiple_extra_tokens() {
             expected: vec!["\")\"".to_string()],
         },
         dropped_tokens: vec![((), Tok::Plus, ()), ((), Tok::Plus, ())],
+        repair: None,
     });
 }
 
@@ -378,6 +564,167 @@ fn error_recovery_dont_panic_on_reduce_at_eof() {
     assert_eq!(errors.len(), 1);
 }
 
+/// Parses `input` with the table-driven and the recursive ascent
+/// parsers for the same grammar, checking that they agree on both the
+/// result and the errors they recovered from.
+fn compare_error_recovery<F, G, R>(input: &str, table: F, ascent: G)
+    where F: FnOnce(&RefCell<Vec<ErrorRecovery<(), Tok, ()>>>, Vec<Tok>)
+                    -> Result<R, ParseError<(), Tok, ()>>,
+          G: FnOnce(&RefCell<Vec<ErrorRecovery<(), Tok, ()>>>, Vec<Tok>)
+                    -> Result<R, ParseError<(), Tok, ()>>,
+          R: Debug + PartialEq
+{
+    let tokens: Vec<_> = util::tok::tokenize(input).into_iter().map(|(_, tok, _)| tok).collect();
+    let table_errors = RefCell::new(vec![]);
+    let table_result = table(&table_errors, tokens.clone());
+    let ascent_errors = RefCell::new(vec![]);
+    let ascent_result = ascent(&ascent_errors, tokens);
+    assert_eq!(ascent_result, table_result, "result for {:?}", input);
+    assert_eq!(ascent_errors, table_errors, "errors for {:?}", input);
+}
+
+#[test]
+fn error_recovery_ascent() {
+    for input in &["+", "---", "--", "-", "(+)", "(++)", "(--)", "(+++)", "(++", "(-", "((--)",
+                   "()", ")", "+-"] {
+        compare_error_recovery(input,
+                               |errors, tokens| error_recovery::parse_Item(errors, tokens),
+                               |errors, tokens| error_recovery_ascent::parse_Item(errors, tokens));
+    }
+}
+
+#[test]
+fn error_recovery_ascent_reduce_on_error() {
+    for input in &["1/", "1+/", "1+", "1+2 3/", "1 2 3/", "/", "1+2/3", ""] {
+        compare_error_recovery(input,
+                               |errors, tokens| {
+                                   let mut errors_182 = vec![];
+                                   let result = error_recovery_pull_182::parse_Item(&mut errors_182,
+                                                                                    tokens);
+                                   errors.borrow_mut().extend(errors_182);
+                                   result
+                               },
+                               |errors, tokens| error_recovery_ascent::parse_Division(errors, tokens));
+    }
+}
+
+/// Parses `input` with the `repair` strategy, expecting a single
+/// recovery with the given repair.
+fn test_error_repair(input: &str,
+                     expected: Vec<&str>,
+                     unrecognized: Option<Tok>,
+                     dropped_tokens: Vec<Tok>,
+                     repair: Repair<(), Tok>) {
+    let errors = RefCell::new(vec![]);
+    let tokens = util::tok::tokenize(input).into_iter().map(|t| t.1);
+    let result = error_recovery_repair::parse_Items(&errors, tokens);
+    let expected: Vec<String> = expected.into_iter().map(|s| s.to_string()).collect();
+    assert_eq!(result, Ok(expected));
+
+    let errors = errors.into_inner();
+    assert_eq!(errors.len(), 1);
+    match errors[0].error {
+        ParseError::UnrecognizedToken { ref token, .. } => {
+            assert_eq!(token.as_ref().map(|t| &t.1), unrecognized.as_ref());
+        }
+        ref error => panic!("unexpected error: {:?}", error),
+    }
+    let dropped_tokens: Vec<_> = dropped_tokens.into_iter().map(|t| ((), t, ())).collect();
+    assert_eq!(errors[0].dropped_tokens, dropped_tokens);
+    assert_eq!(errors[0].repair, Some(repair));
+}
+
+#[test]
+fn error_repair_insert() {
+    test_error_repair("(+ +",
+                      vec!["(!)", "+"],
+                      Some(Tok::Plus),
+                      vec![],
+                      Repair::Insert(((), Tok::RParen, ())));
+}
+
+#[test]
+fn error_repair_insert_at_eof() {
+    test_error_repair("+ (+",
+                      vec!["+", "(!)"],
+                      None,
+                      vec![],
+                      Repair::Insert(((), Tok::RParen, ())));
+}
+
+#[test]
+fn error_repair_delete() {
+    test_error_repair("(/) +",
+                      vec!["(!)", "+"],
+                      Some(Tok::Div),
+                      vec![Tok::Div],
+                      Repair::Delete(((), Tok::Div, ())));
+}
+
+#[test]
+fn error_repair_substitute() {
+    test_error_repair("(/ +",
+                      vec!["(!)", "+"],
+                      Some(Tok::Div),
+                      vec![Tok::Div],
+                      Repair::Substitute {
+                          token: ((), Tok::Div, ()),
+                          replacement: ((), Tok::RParen, ()),
+                      });
+}
+
+#[test]
+fn error_repair_substitute_at_eof() {
+    test_error_repair("(/",
+                      vec!["(!)"],
+                      Some(Tok::Div),
+                      vec![Tok::Div],
+                      Repair::Substitute {
+                          token: ((), Tok::Div, ()),
+                          replacement: ((), Tok::RParen, ()),
+                      });
+}
+
+#[test]
+fn error_repair_falls_back_to_panic_mode() {
+    // no single token repairs `(/ / )`, so both `/` are dropped
+    let errors = RefCell::new(vec![]);
+    util::test(|v| error_recovery_repair::parse_Items(&errors, v),
+               "(//)",
+               vec!["(!)".to_string()]);
+
+    let errors = errors.into_inner();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].dropped_tokens, vec![((), Tok::Div, ()), ((), Tok::Div, ())]);
+    assert_eq!(errors[0].repair, None);
+}
+
+#[test]
+fn error_recovery_limit_counts_recoveries() {
+    let errors = RefCell::new(vec![]);
+    util::test(|v| error_recovery_limit::parse_Items(&errors, v),
+               "(+) (-)",
+               (1, vec!["(+)".to_string(), "(!)".to_string()]));
+    assert_eq!(errors.borrow().len(), 1);
+
+    let errors = RefCell::new(vec![]);
+    util::test(|v| error_recovery_limit::parse_Items(&errors, v),
+               "(-) (--) +",
+               (2, vec!["(!)".to_string(), "(!)".to_string(), "+".to_string()]));
+    assert_eq!(errors.borrow().len(), 2);
+}
+
+#[test]
+fn error_recovery_limit_exceeded() {
+    let errors = RefCell::new(vec![]);
+    let tokens = util::tok::tokenize("(-) (-) (+) (-)").into_iter().map(|t| t.1);
+    match error_recovery_limit::parse_Items(&errors, tokens) {
+        Err(ParseError::UnrecognizedToken { token: Some(((), Tok::Minus, ())), .. }) => { }
+        r => panic!("unexpected result: {:?}", r),
+    }
+    assert_eq!(errors.borrow().len(), 2);
+}
+
 #[test]
 fn issue_55_test1() {
     // Issue 55 caused us to either accept NO assoc types or assoc
@@ -401,6 +748,11 @@ fn unit_test1() {
     assert!(unit::parse_Expr("3 + +").is_err());
 }
 
+#[test]
+fn lalrpop_mod_test1() {
+    assert_eq!(out_dir_sum::parse_Sum("1 + 2 + 3").unwrap(), 6);
+}
+
 #[test]
 fn generics_issue_104_test1() {
     // The real thing `generics_issue_104` is testing is that the code
@@ -90,6 +90,79 @@ where L: fmt::Debug + fmt::Display,
 pub struct ErrorRecovery<L, T, E> {
     pub error: ParseError<L, T, E>,
     pub dropped_tokens: Vec<(L, T, L)>,
+
+    /// The single-token repair that let the parser resume after the
+    /// `!`. Only grammars annotated with
+    /// `#[recovery(strategy="repair")]` look for one; otherwise (or if
+    /// none was found) this is `None`.
+    pub repair: Option<Repair<L, T>>,
+}
+
+/// A change to the input that the parser made while recovering from
+/// an error.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Repair<L, T> {
+    /// The token was inserted in front of the token that caused the
+    /// error. Its span is empty, at the start of that token.
+    Insert((L, T, L)),
+
+    /// The token that caused the error was deleted.
+    Delete((L, T, L)),
+
+    /// The token that caused the error was replaced by another one,
+    /// with the same span.
+    Substitute {
+        token: (L, T, L),
+        replacement: (L, T, L),
+    },
+}
+
+/// Declares a module containing a parser that LALRPOP generated into
+/// `OUT_DIR`, as it does when the build script uses
+/// `Configuration::use_cargo_dir_conventions`. For example,
+///
+/// ```ignore
+/// #[macro_use] extern crate lalrpop_util;
+///
+/// lalrpop_mod!(pub calculator);
+/// ```
+///
+/// declares `pub mod calculator` with the parser generated from
+/// `src/calculator.lalrpop`. The path of a generated file, relative to
+/// `OUT_DIR`, is that of its grammar relative to `src`, so for
+/// `src/lang/parser.lalrpop` you would write:
+///
+/// ```ignore
+/// lalrpop_mod!(parser, "/lang/parser.rs");
+/// ```
+///
+/// Attributes, including doc comments, may be given before the name of
+/// the module. The lints that generated code is known to trip (unused
+/// imports in action code, say) are allowed within it.
+#[macro_export]
+macro_rules! lalrpop_mod {
+    ($(#[$attr:meta])* pub $modname:ident) => {
+        $crate::lalrpop_mod!($(#[$attr])* pub $modname, concat!("/", stringify!($modname), ".rs"));
+    };
+    ($(#[$attr:meta])* $modname:ident) => {
+        $crate::lalrpop_mod!($(#[$attr])* $modname, concat!("/", stringify!($modname), ".rs"));
+    };
+    ($(#[$attr:meta])* pub $modname:ident, $source:expr) => {
+        #[allow(dead_code, unused_imports, unused_mut, unused_variables,
+                non_snake_case, non_camel_case_types)]
+        $(#[$attr])*
+        pub mod $modname {
+            include!(concat!(env!("OUT_DIR"), $source));
+        }
+    };
+    ($(#[$attr:meta])* $modname:ident, $source:expr) => {
+        #[allow(dead_code, unused_imports, unused_mut, unused_variables,
+                non_snake_case, non_camel_case_types)]
+        $(#[$attr])*
+        mod $modname {
+            include!(concat!(env!("OUT_DIR"), $source));
+        }
+    };
 }
 
 #[cfg(test)]
@@ -1,10 +1,11 @@
-use build;
+use build::{self, Error, InterpretError, ParseTree};
 use log::Level;
 use session::{ColorConfig, Session};
 use std::default::Default;
 use std::env;
 use std::env::current_dir;
-use std::error::Error;
+use std::error;
+use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
@@ -63,14 +64,16 @@ impl Configuration {
 
     /// Apply `cargo` directory location conventions, by setting the
     /// input directory to `src` and the output directory to
-    /// `$OUT_DIR`.
+    /// `$OUT_DIR`. The parser for `src/foo.lalrpop` can then be
+    /// declared as a module with `lalrpop_mod!(foo)`, using the macro
+    /// exported by `lalrpop-util`.
     pub fn use_cargo_dir_conventions(&mut self) -> &mut Self {
         self.set_in_dir("src").set_out_dir(env::var("OUT_DIR").unwrap());
         self
     }
 
     /// If true, always convert `.lalrpop` files into `.rs` files, even if the
-    /// `.rs` file is newer. Default is false.
+    /// `.rs` file was generated from the same sources. Default is false.
     pub fn force_build(&mut self, val: bool) -> &mut Configuration {
         self.session.force_build = val;
         self
@@ -83,6 +86,42 @@ impl Configuration {
         self
     }
 
+    /// If true, write a human-readable report of the LR(1) states and
+    /// of any conflicts next to each generated file, as `foo.lalrpop.txt`
+    /// for `foo.rs`. The report is written even if the grammar has
+    /// conflicts. Default is false.
+    pub fn emit_report(&mut self, val: bool) -> &mut Configuration {
+        self.session.emit_report = val;
+        self
+    }
+
+    /// If true, write graphviz `.dot` files next to each generated
+    /// file: `foo.Expr.dot` for the LR(1) states of public nonterminal
+    /// `Expr` (each labeled with its kernel items), and `foo.lexer.dot`
+    /// for the DFA of the generated tokenizer, if any. Default is
+    /// false.
+    pub fn emit_dot(&mut self, val: bool) -> &mut Configuration {
+        self.session.emit_dot = val;
+        self
+    }
+
+    /// If true, report warnings as errors, so that a grammar with
+    /// warnings fails to build. A grammar can also do this for a
+    /// single lint with `#[deny(lint="..")]`, or silence one with
+    /// `#[allow(lint="..")]`. Default is false.
+    pub fn deny_warnings(&mut self, val: bool) -> &mut Configuration {
+        self.session.deny_warnings = val;
+        self
+    }
+
+    /// Process up to `val` `.lalrpop` files at the same time when
+    /// processing a directory. Default is 1, which processes them one
+    /// after the other on the calling thread.
+    pub fn set_jobs(&mut self, val: usize) -> &mut Configuration {
+        self.session.jobs = val;
+        self
+    }
+
     /// Minimal logs: only for errors that halt progress.
     pub fn log_quiet(&mut self) -> &mut Configuration {
         self.session.log.set_level(Level::Taciturn);
@@ -119,7 +158,12 @@ impl Configuration {
 
     /// Process all files according to the `set_in_dir` and
     /// `set_out_dir` configuration.
-    pub fn process(&self) -> Result<(), Box<Error>> {
+    ///
+    /// Errors in the grammars are returned as `Error::Grammar`, which
+    /// carries a `Diagnostic` for each problem found, rather than
+    /// ending the process. They are not printed; only warnings and
+    /// log messages are.
+    pub fn process(&self) -> Result<(), Error> {
         let root = if let Some(ref d) = self.session.in_dir {
             d.as_path()
         } else {
@@ -130,31 +174,56 @@ impl Configuration {
 
     /// Process all files in the current directory, which -- unless you
     /// have changed it -- is typically the root of the crate being compiled.
-    pub fn process_current_dir(&self) -> Result<(), Box<Error>> {
+    pub fn process_current_dir(&self) -> Result<(), Error> {
         self.process_dir(try!(current_dir()))
     }
 
-    /// Process all `.lalrpop` files in `path`.
-    pub fn process_dir<P:AsRef<Path>>(&self, path: P) -> Result<(), Box<Error>> {
+    /// Process all `.lalrpop` files in `path`. A file with errors
+    /// does not stop the remaining files from being processed.
+    pub fn process_dir<P:AsRef<Path>>(&self, path: P) -> Result<(), Error> {
         let session = Rc::new(self.session.clone());
-        try!(build::process_dir(session, path));
-        Ok(())
+        build::process_dir(session, path)
     }
 
     /// Process the given `.lalrpop` file.
-    pub fn process_file<P:AsRef<Path>>(&self, path: P) -> Result<(), Box<Error>> {
+    pub fn process_file<P:AsRef<Path>>(&self, path: P) -> Result<(), Error> {
         let session = Rc::new(self.session.clone());
-        try!(build::process_file(session, path));
-        Ok(())
+        build::process_file(session, path)
+    }
+
+    /// Generate the parser for the grammar `text`, held in memory
+    /// rather than in a `.lalrpop` file, and return its Rust code.
+    /// Neither the filesystem nor the console is touched, so the
+    /// grammar cannot `import` other files, and any errors are
+    /// returned rather than printed.
+    pub fn process_str(&self, text: &str) -> Result<String, Error> {
+        let session = Rc::new(self.session.clone());
+        build::process_str(session, text)
+    }
+
+    /// Parse `terminals`, separated by whitespace, starting from the
+    /// nonterminal `start` of the grammar in the given `.lalrpop`
+    /// file, without generating any code. The result is the parse
+    /// tree, or where parsing stopped and what was expected there;
+    /// errors in the grammar itself are returned as such.
+    pub fn interpret_file<P: AsRef<Path>>(&self,
+                                          path: P,
+                                          start: &str,
+                                          terminals: &str)
+                                          -> Result<Result<ParseTree, InterpretError>, Error> {
+        let session = Rc::new(self.session.clone());
+        build::interpret_file(session, path.as_ref(), start, terminals)
     }
 }
 
 /// Process all files in the current directory, which -- unless you
 /// have changed it -- is typically the root of the crate being compiled.
 ///
-/// Equivalent to `Configuration::new().process_current_dir()`.
-pub fn process_root() -> Result<(), Box<Error>> {
-    Configuration::new().process_current_dir()
+/// Equivalent to `Configuration::new().process_current_dir()`, except
+/// that the report of each error in the grammars is printed to stderr.
+pub fn process_root() -> Result<(), Box<error::Error>> {
+    try!(print_diagnostics(Configuration::new().process_current_dir()));
+    Ok(())
 }
 
 /// Deprecated in favor of `Configuration`. Try:
@@ -164,6 +233,20 @@ pub fn process_root() -> Result<(), Box<Error>> {
 /// ```
 ///
 /// instead.
-pub fn process_root_unconditionally() -> Result<(), Box<Error>> {
-    Configuration::new().force_build(true).process_current_dir()
+pub fn process_root_unconditionally() -> Result<(), Box<error::Error>> {
+    try!(print_diagnostics(Configuration::new().force_build(true).process_current_dir()));
+    Ok(())
+}
+
+/// Prints the full report of each problem `result` found in the
+/// grammars, if any, before passing it on.
+fn print_diagnostics(result: Result<(), Error>) -> Result<(), Error> {
+    if let Err(Error::Grammar(ref diagnostics)) = result {
+        let stderr = io::stderr();
+        let mut stderr = stderr.lock();
+        for diagnostic in diagnostics {
+            let _ = writeln!(stderr, "{}", diagnostic.rendered);
+        }
+    }
+    result
 }
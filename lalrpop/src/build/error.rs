@@ -0,0 +1,82 @@
+//! The errors reported by `Configuration` when processing fails.
+
+use grammar::parse_tree::Span;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::PathBuf;
+
+/// A problem found in a `.lalrpop` file, such as a syntax error or
+/// an LR(1) conflict.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Diagnostic {
+    /// The `.lalrpop` file in which the problem was found, or
+    /// `<input>` for a grammar processed from a string.
+    pub file: PathBuf,
+
+    /// The part of `file` the problem concerns, as byte offsets into
+    /// that file.
+    pub span: Span,
+
+    /// A one-line summary of the problem.
+    pub message: String,
+
+    /// The full report of the problem, as LALRPOP prints it,
+    /// without any colors.
+    pub rendered: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        write!(fmt, "{}: {}", self.file.display(), self.message)
+    }
+}
+
+/// The ways in which processing `.lalrpop` files can fail.
+#[derive(Debug)]
+pub enum Error {
+    /// Reading the input or writing the output failed.
+    Io(io::Error),
+
+    /// The input contained errors; each is reported separately.
+    Grammar(Vec<Diagnostic>),
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Error {
+        Error::Io(error)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, fmt: &mut Formatter) -> fmt::Result {
+        match *self {
+            Error::Io(ref error) => write!(fmt, "{}", error),
+            Error::Grammar(ref diagnostics) => {
+                for (index, diagnostic) in diagnostics.iter().enumerate() {
+                    if index > 0 {
+                        try!(writeln!(fmt, ""));
+                    }
+                    try!(write!(fmt, "{}", diagnostic));
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Io(ref error) => error.description(),
+            Error::Grammar(_) => "errors found in grammar",
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            Error::Io(ref error) => Some(error),
+            Error::Grammar(_) => None,
+        }
+    }
+}
@@ -0,0 +1,183 @@
+//! Runs the parser for a grammar on a sequence of terminals, using
+//! the LR(1) interpreter rather than generated code, so that grammars
+//! can be tried out without rebuilding anything.
+
+use file_text::FileText;
+use grammar::consts::INLINE;
+use grammar::parse_tree as pt;
+use grammar::repr as r;
+use intern::intern;
+use lr1;
+use session::Session;
+use std::fmt;
+use std::path::Path;
+use std::rc::Rc;
+use tls::Tls;
+use util::Sep;
+
+use super::{expected_suffix, normalize_grammar, parse_grammar, report_conflicts, report_error,
+            Error};
+
+/// The parse tree of a sequence of terminals. The nonterminals are
+/// those of the grammar after macro expansion, so `Comma<Expr>` or
+/// `("," Expr)*` show up as nonterminals of their own.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseTree {
+    Nonterminal(String, Vec<ParseTree>),
+    Terminal(String),
+}
+
+/// Where a sequence of terminals stopped parsing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterpretError {
+    /// The index of the terminal that could not be parsed, or the
+    /// number of terminals if the input ended too early.
+    pub position: usize,
+
+    /// The terminal that could not be parsed, or `None` if the input
+    /// ended too early.
+    pub found: Option<String>,
+
+    /// The terminals that would have been accepted at `position`.
+    pub expected: Vec<String>,
+}
+
+/// Parses `terminals`, separated by whitespace, starting from the
+/// nonterminal `start` of the grammar in `lalrpop_file`, which need
+/// not be public but cannot be `#[inline]`. Each terminal is written as in the grammar, like
+/// `"("` or `Num`, except that the quotes of a string literal may be
+/// left out. Problems with the grammar itself are reported as
+/// errors, the same as when generating its parser.
+pub fn interpret_file(session: Rc<Session>,
+                      lalrpop_file: &Path,
+                      start: &str,
+                      terminals: &str)
+                      -> Result<Result<ParseTree, InterpretError>, Error> {
+    let mut file_text = try!(FileText::from_path(lalrpop_file.to_path_buf()));
+    let mut grammar = try!(parse_grammar(&mut file_text));
+    let file_text = Rc::new(file_text);
+    let _tls = Tls::install(session.clone(), file_text.clone());
+
+    // making `start` public gives it a start symbol of its own; that
+    // is not possible for an inline nonterminal, which is gone by the
+    // time the states are built
+    let start = pt::NonterminalString(intern(start));
+    let mut found = false;
+    for item in &mut grammar.items {
+        if let pt::GrammarItem::Nonterminal(ref mut data) = *item {
+            if data.name == start && data.args.is_empty() {
+                if data.annotations.iter().any(|annotation| annotation.id == intern(INLINE)) {
+                    return Err(report_error(&file_text,
+                                            data.span,
+                                            &format!("cannot interpret inline nonterminal `{}`",
+                                                     start)));
+                }
+                data.public = true;
+                found = true;
+            }
+        }
+    }
+    if !found {
+        return Err(report_error(&file_text,
+                                grammar.span,
+                                &format!("no nonterminal `{}` to start from", start)));
+    }
+
+    let grammar = try!(normalize_grammar(&session, &file_text, grammar));
+    let start_nt = grammar.start_nonterminals[&start];
+    let _lr1_tls = lr1::Lr1Tls::install(grammar.terminals.clone());
+    let states = match lr1::build_states(&grammar, start_nt) {
+        Ok(states) => states,
+        Err(error) => return Err(report_conflicts(&grammar, &error)),
+    };
+
+    let words: Vec<&str> = terminals.split_whitespace().collect();
+    let error = |state: &lr1::LR1State, position: usize| {
+        InterpretError {
+            position: position,
+            found: words.get(position).map(|word| word.to_string()),
+            expected: lr1::expected_terminals(&grammar, state)
+                .iter()
+                .map(|terminal| terminal.to_string())
+                .collect(),
+        }
+    };
+
+    let mut tokens = vec![];
+    for &word in &words {
+        match terminal(&grammar, word) {
+            Some(terminal) => tokens.push(terminal),
+            None => {
+                // the parse cannot go past a terminal that is not in
+                // the grammar, if it gets that far
+                let position = tokens.len();
+                return Ok(Err(match lr1::interpret_partial(&states, tokens) {
+                    Ok(stack) => error(&states[stack.last().unwrap().0], position),
+                    Err((state, position, _)) => error(state, position),
+                }));
+            }
+        }
+    }
+
+    Ok(match lr1::interpret(&states, tokens) {
+        Ok(lr1::ParseTree::Nonterminal(nonterminal, mut trees)) => {
+            // leave out the start symbol we added
+            assert!(nonterminal == start_nt && trees.len() == 1);
+            Ok(convert(trees.pop().unwrap()))
+        }
+        Ok(tree) => Ok(convert(tree)),
+        Err((state, position, _)) => Err(error(state, position)),
+    })
+}
+
+/// The terminal of `grammar` that `word` stands for, if any.
+fn terminal(grammar: &r::Grammar, word: &str) -> Option<r::TerminalString> {
+    grammar.terminals
+           .all
+           .iter()
+           .cloned()
+           .find(|&terminal| {
+               terminal.to_string() == word ||
+                   match terminal {
+                       r::TerminalString::Literal(pt::TerminalLiteral::Quoted(s)) => {
+                           s.to_string() == word
+                       }
+                       _ => false,
+                   }
+           })
+}
+
+fn convert(tree: lr1::ParseTree) -> ParseTree {
+    match tree {
+        lr1::ParseTree::Nonterminal(nonterminal, trees) => {
+            ParseTree::Nonterminal(nonterminal.to_string(), trees.into_iter().map(convert).collect())
+        }
+        lr1::ParseTree::Terminal(terminal) => ParseTree::Terminal(terminal.to_string()),
+    }
+}
+
+impl fmt::Display for ParseTree {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ParseTree::Nonterminal(ref id, ref trees) => {
+                write!(fmt, "[{}: {}]", id, Sep(", ", trees))
+            }
+            ParseTree::Terminal(ref id) => write!(fmt, "{}", id),
+        }
+    }
+}
+
+impl fmt::Display for InterpretError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.found {
+            Some(ref terminal) => {
+                write!(fmt,
+                       "unexpected terminal `{}` at position {}{}",
+                       terminal,
+                       self.position,
+                       expected_suffix(&self.expected))
+            }
+            None => write!(fmt, "unexpected end of input{}", expected_suffix(&self.expected)),
+        }
+    }
+}
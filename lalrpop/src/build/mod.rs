@@ -1,11 +1,13 @@
 //! Utilies for running in a build script.
 
 use atty;
+use collections::{set, Set};
 use file_text::FileText;
 use grammar::parse_tree as pt;
 use grammar::repr as r;
 use lalrpop_util::ParseError;
 use lexer::intern_token;
+use log;
 use lr1;
 use message::{Content, Message};
 use message::builder::InlineBuilder;
@@ -13,30 +15,130 @@ use normalize;
 use parser;
 use rust::RustWrite;
 use session::{ColorConfig, Session};
-use term;
+use term::{self, Terminal};
 use tls::Tls;
 use tok;
 
+use std::cmp;
+use std::env;
 use std::fs;
-use std::io::{self, Write};
+use std::io::{self, BufRead, Read, Write};
+use std::panic;
 use std::path::{Path, PathBuf};
-use std::process::exit;
+use std::process;
 use std::rc::Rc;
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
 
 mod action;
+mod error;
 mod fake_term;
+mod interpret;
 
+#[cfg(test)]
+mod test;
+
+pub use self::error::{Diagnostic, Error};
+pub use self::interpret::{interpret_file, InterpretError, ParseTree};
 use self::fake_term::FakeTerminal;
 
-pub fn process_dir<P: AsRef<Path>>(session: Rc<Session>, root_dir: P) -> io::Result<()> {
+/// Processes every `.lalrpop` file under `root_dir`, using up to
+/// `session.jobs` threads. A file that contains errors does not stop
+/// the others from being processed; the diagnostics for all files are
+/// reported together, in the order the files were found.
+///
+/// When called from a build script, which is when `OUT_DIR` is set,
+/// it also tells cargo to rerun the script only when one of those
+/// files changes. The directory itself is not listed, since it
+/// usually contains `target`, which changes on every build; a new
+/// grammar is only picked up once something else reruns the script.
+pub fn process_dir<P: AsRef<Path>>(session: Rc<Session>, root_dir: P) -> Result<(), Error> {
     let lalrpop_files = try!(lalrpop_files(root_dir));
-    for lalrpop_file in lalrpop_files {
-        try!(process_file(session.clone(), lalrpop_file));
+    if env::var_os("OUT_DIR").is_some() {
+        let stdout = io::stdout();
+        try!(write_rerun_directives(&mut stdout.lock(), &lalrpop_files));
+    }
+    let mut diagnostics = vec![];
+    for result in process_files(&session, lalrpop_files) {
+        match result {
+            Ok(()) => { }
+            Err(Error::Grammar(file_diagnostics)) => diagnostics.extend(file_diagnostics),
+            Err(error) => return Err(error),
+        }
+    }
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Grammar(diagnostics))
     }
-    Ok(())
 }
 
-pub fn process_file<P: AsRef<Path>>(session: Rc<Session>, lalrpop_file: P) -> io::Result<()> {
+/// Processes `lalrpop_files` in parallel, returning the result for
+/// each. Files share no state while being processed: each thread has
+/// its own copy of the session, and the TLS and interner used by the
+/// rest of LALRPOP are per-thread anyway. What is printed for a file is
+/// held back until it is done, and then printed in one piece.
+fn process_files(session: &Rc<Session>, lalrpop_files: Vec<PathBuf>) -> Vec<Result<(), Error>> {
+    let jobs = cmp::min(session.jobs, lalrpop_files.len());
+    if jobs <= 1 {
+        return lalrpop_files.into_iter()
+                            .map(|lalrpop_file| process_file(session.clone(), lalrpop_file))
+                            .collect();
+    }
+
+    let num_files = lalrpop_files.len();
+    let queue = Arc::new(Mutex::new(lalrpop_files.into_iter().enumerate()));
+    let (sender, receiver) = mpsc::channel();
+    let workers: Vec<_> =
+        (0..jobs)
+            .map(|_| {
+                let session = (**session).clone();
+                let queue = queue.clone();
+                let sender = sender.clone();
+                thread::spawn(move || {
+                    // Running workers side by side is only sound
+                    // because all the global state LALRPOP uses is
+                    // thread-local: the `Tls` that `process_file`
+                    // installs for each file, the string interner, the
+                    // terminal set of `lr1::tls` and the output being
+                    // captured. Nothing checks this, so any new global
+                    // must be thread-local too. For the same reason,
+                    // what a worker sends back must not contain
+                    // `InternedString`s, which only make sense in the
+                    // interner of the thread that created them.
+                    let session = Rc::new(session);
+                    loop {
+                        let next = queue.lock().unwrap().next();
+                        let (index, lalrpop_file) = match next {
+                            Some(next) => next,
+                            None => break,
+                        };
+                        let (result, output) =
+                            log::capture(|| process_file(session.clone(), lalrpop_file));
+                        if sender.send((index, result, output)).is_err() {
+                            break;
+                        }
+                    }
+                })
+            })
+            .collect();
+    drop(sender);
+
+    let mut results: Vec<_> = (0..num_files).map(|_| None).collect();
+    for (index, result, output) in receiver {
+        let stdout = io::stdout();
+        let _ = stdout.lock().write_all(&output);
+        results[index] = Some(result);
+    }
+    for worker in workers {
+        if let Err(payload) = worker.join() {
+            panic::resume_unwind(payload);
+        }
+    }
+    results.into_iter().map(|result| result.unwrap()).collect()
+}
+
+pub fn process_file<P: AsRef<Path>>(session: Rc<Session>, lalrpop_file: P) -> Result<(), Error> {
     let lalrpop_file = lalrpop_file.as_ref();
     let rs_file = try!(resolve_rs_file(&session, lalrpop_file));
     process_file_into(session, lalrpop_file, &rs_file)
@@ -61,93 +163,260 @@ fn resolve_rs_file(session: &Session, lalrpop_file: &Path) -> io::Result<PathBuf
               .with_extension("rs"))
 }
 
-fn process_file_into(session: Rc<Session>, lalrpop_file: &Path, rs_file: &Path) -> io::Result<()> {
-    if session.force_build || try!(needs_rebuild(&lalrpop_file, &rs_file)) {
+fn process_file_into(session: Rc<Session>,
+                     lalrpop_file: &Path,
+                     rs_file: &Path)
+                     -> Result<(), Error> {
+    if !session.force_build && try!(is_up_to_date(&session, lalrpop_file, rs_file)) {
+        return Ok(());
+    }
+
+    log!(session,
+         Informative,
+         "processing file `{}`",
+         lalrpop_file.to_string_lossy());
+
+    // Load the LALRPOP source text for this file, along with that of
+    // the files it imports.
+    let mut file_text = try!(FileText::from_path(lalrpop_file.to_path_buf()));
+    let grammar = try!(parse_grammar(&mut file_text));
+    let hash = source_hash(&session,
+                           Some(&file_text).into_iter()
+                                           .chain(file_text.imports())
+                                           .map(|text| text.text().as_bytes()));
+    let imports: Vec<_> = file_text.imports().map(|text| text.path().to_path_buf()).collect();
+    if let Some(parent) = rs_file.parent() {
+        try!(fs::create_dir_all(parent));
+    }
+
+    // Do the LALRPOP processing itself and write the resulting
+    // buffer into a file. We use a buffer so that if LR(1)
+    // generation fails at some point, we don't leave a partial
+    // file behind.
+    let mut debug_files = DebugFiles::default();
+    let buffer = generate(&session, file_text, grammar, &mut debug_files);
+    try!(debug_files.write(&session, rs_file));
+    let mut output = header(&hash, &imports).into_bytes();
+    output.extend(try!(buffer));
+    if !try!(write_if_changed(rs_file, &output)) {
         log!(session,
-             Informative,
-             "processing file `{}`",
-             lalrpop_file.to_string_lossy());
-        if let Some(parent) = rs_file.parent() {
-            try!(fs::create_dir_all(parent));
+             Verbose,
+             "`{}` is up to date",
+             rs_file.to_string_lossy());
+    }
+    Ok(())
+}
+
+/// Whether `rs_file` was generated from the current contents of
+/// `lalrpop_file` and of the files it imports. The header of
+/// `rs_file` lists those imports, so this only reads the files,
+/// without parsing any of them: if an import was added or removed,
+/// the text of the file that imports it changed too.
+fn is_up_to_date(session: &Session, lalrpop_file: &Path, rs_file: &Path) -> io::Result<bool> {
+    let (hash, imports) = match try!(embedded_header(rs_file)) {
+        Some(header) => header,
+        None => return Ok(false),
+    };
+
+    let mut texts = vec![try!(read_bytes(lalrpop_file))];
+    for import in imports {
+        match read_bytes(&import) {
+            Ok(text) => texts.push(text),
+            // processing the grammar reports this properly, if the
+            // file is still imported at all
+            Err(_) => return Ok(false),
         }
-        try!(make_read_only(&rs_file, false));
-        try!(remove_old_file(&rs_file));
-
-        // Load the LALRPOP source text for this file:
-        let file_text = Rc::new(try!(FileText::from_path(lalrpop_file.to_path_buf())));
-
-        // Store the session and file-text in TLS -- this is not
-        // intended to be used in this high-level code, but it gives
-        // easy access to this information pervasively in the
-        // low-level LR(1) and grammar normalization code. This is
-        // particularly useful for error-reporting.
-        let _tls = Tls::install(session.clone(), file_text.clone());
-
-        // Do the LALRPOP processing itself and write the resulting
-        // buffer into a file. We use a buffer so that if LR(1)
-        // generation fails at some point, we don't leave a partial
-        // file behind.
-        {
-            let grammar = try!(parse_and_normalize_grammar(&session, &file_text));
-            let buffer = try!(emit_recursive_ascent(&session, &grammar));
-            let mut output_file = try!(fs::File::create(&rs_file));
-            try!(output_file.write_all(&buffer));
+    }
+    Ok(source_hash(session, texts.iter().map(|text| &text[..])) == hash)
+}
+
+fn read_bytes(path: &Path) -> io::Result<Vec<u8>> {
+    let mut bytes = vec![];
+    try!(try!(fs::File::open(path)).read_to_end(&mut bytes));
+    Ok(bytes)
+}
+
+/// The name given to grammar text that does not come from a file,
+/// which is what error messages refer to it as.
+const STR_INPUT: &'static str = "<input>";
+
+/// Generates the parser for the grammar `text`, returning the Rust
+/// code rather than writing it anywhere. Since there is no file to
+/// resolve them against, the grammar may not contain `import`s, and
+/// the report and graphs asked for by the session are not written.
+/// Nothing is printed either: each error comes with its rendered
+/// text, and warnings are dropped unless the session denies them.
+pub fn process_str(session: Rc<Session>, text: &str) -> Result<String, Error> {
+    let (result, _output) = log::capture(|| {
+        let file_text = FileText::new(PathBuf::from(STR_INPUT), text.to_string());
+        let grammar = try!(parse_file(&file_text, 0));
+        for item in &grammar.items {
+            if let pt::GrammarItem::Import(ref data) = *item {
+                return Err(report_error(&file_text,
+                                        data.span,
+                                        "grammars processed from a string cannot import files"));
+            }
         }
+        let buffer = try!(generate(&session, file_text, grammar, &mut DebugFiles::default()));
+        Ok(String::from_utf8(buffer).unwrap())
+    });
+    result
+}
 
-        try!(make_read_only(&rs_file, true));
-    }
-    Ok(())
+/// Normalizes `grammar`, whose source text is `file_text`, and
+/// generates its parser into a buffer.
+fn generate(session: &Rc<Session>,
+            file_text: FileText,
+            grammar: pt::Grammar,
+            debug_files: &mut DebugFiles)
+            -> Result<Vec<u8>, Error> {
+    let file_text = Rc::new(file_text);
+
+    // Store the session and file-text in TLS -- this is not
+    // intended to be used in this high-level code, but it gives
+    // easy access to this information pervasively in the
+    // low-level LR(1) and grammar normalization code. This is
+    // particularly useful for error-reporting.
+    let _tls = Tls::install(session.clone(), file_text.clone());
+
+    let grammar = try!(normalize_grammar(session, &file_text, grammar));
+    emit_recursive_ascent(session, &grammar, debug_files)
 }
 
-fn remove_old_file(rs_file: &Path) -> io::Result<()> {
-    match fs::remove_file(rs_file) {
-        Ok(()) => Ok(()),
-        Err(e) => {
-            // Unix reports NotFound, Windows PermissionDenied!
-            match e.kind() {
-                io::ErrorKind::NotFound | io::ErrorKind::PermissionDenied => Ok(()),
-                _ => Err(e),
+/// Replaces the contents of the (read-only) `rs_file` with `buffer`,
+/// unless they are the same already, so that the crates using it are
+/// not rebuilt for nothing. The new contents are written to a
+/// temporary file which is then renamed into place, so that an
+/// interrupted build never leaves a truncated file behind. Returns
+/// whether the file was written.
+fn write_if_changed(rs_file: &Path, buffer: &[u8]) -> io::Result<bool> {
+    match fs::File::open(rs_file) {
+        Ok(mut file) => {
+            let mut old_buffer = vec![];
+            try!(file.read_to_end(&mut old_buffer));
+            if old_buffer == buffer {
+                return Ok(false);
             }
         }
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => { }
+        Err(e) => return Err(e),
+    }
+
+    let temp_file = rs_file.with_extension(format!("rs.{}.tmp", process::id()));
+    let result = fs::File::create(&temp_file)
+        .and_then(|mut file| file.write_all(buffer))
+        .and_then(|()| make_read_only(&temp_file, true))
+        // Windows refuses to replace a read-only file
+        .and_then(|()| make_read_only(rs_file, false))
+        .and_then(|()| fs::rename(&temp_file, rs_file));
+    if result.is_err() {
+        let _ = make_read_only(&temp_file, false);
+        let _ = fs::remove_file(&temp_file);
     }
+    result.map(|()| true)
+}
+
+/// The debugging aids that the session asks us to write next to the
+/// generated file. They are written even if generating the parser
+/// fails, since that is when they are most useful.
+#[derive(Default)]
+struct DebugFiles {
+    /// The LR(1) states and conflicts, for `foo.lalrpop.txt`.
+    report: Vec<u8>,
+
+    /// Graphs as `(extension, contents)`, for `foo.Expr.dot` and the
+    /// like.
+    graphs: Vec<(String, String)>,
 }
 
-fn needs_rebuild(lalrpop_file: &Path, rs_file: &Path) -> io::Result<bool> {
-    return match fs::metadata(&rs_file) {
-        Ok(rs_metadata) => {
-            let lalrpop_metadata = try!(fs::metadata(&lalrpop_file));
-            Ok(compare_modification_times(&lalrpop_metadata, &rs_metadata))
+impl DebugFiles {
+    fn write(&self, session: &Session, rs_file: &Path) -> io::Result<()> {
+        if session.emit_report {
+            let report_file = rs_file.with_extension("lalrpop.txt");
+            try!(fs::File::create(&report_file).and_then(|mut f| f.write_all(&self.report)));
         }
-        Err(e) => {
-            match e.kind() {
-                io::ErrorKind::NotFound => Ok(true),
-                _ => Err(e),
-            }
+        for &(ref extension, ref graph) in &self.graphs {
+            let dot_file = rs_file.with_extension(extension);
+            try!(fs::File::create(&dot_file).and_then(|mut f| f.write_all(graph.as_bytes())));
         }
-    };
+        Ok(())
+    }
+}
+
+const HASH_PREFIX: &'static str = "// source hash: ";
+const IMPORT_PREFIX: &'static str = "// imports: ";
 
-    #[cfg(unix)]
-    fn compare_modification_times(lalrpop_metadata: &fs::Metadata,
-                                  rs_metadata: &fs::Metadata)
-                                  -> bool {
-        use std::os::unix::fs::MetadataExt;
-        lalrpop_metadata.mtime() >= rs_metadata.mtime()
+/// The first lines of each generated file. They record the
+/// `source_hash` it was generated from, which is how we tell whether
+/// it is up to date: unlike modification times, it survives `git
+/// checkout`, caches and the like. The files the grammar imports,
+/// which the hash covers, follow one per line.
+fn header(hash: &str, imports: &[PathBuf]) -> String {
+    let mut header = format!("// auto-generated by lalrpop {}\n{}{}\n",
+                             env!("CARGO_PKG_VERSION"),
+                             HASH_PREFIX,
+                             hash);
+    for import in imports {
+        header.push_str(&format!("{}{}\n", IMPORT_PREFIX, import.display()));
     }
+    header
+}
 
-    #[cfg(windows)]
-    fn compare_modification_times(lalrpop_metadata: &fs::Metadata,
-                                  rs_metadata: &fs::Metadata)
-                                  -> bool {
-        use std::os::windows::fs::MetadataExt;
-        lalrpop_metadata.last_write_time() >= rs_metadata.last_write_time()
+/// Reads the hash and the imported files from the header of
+/// `rs_file`, if there is one.
+fn embedded_header(rs_file: &Path) -> io::Result<Option<(String, Vec<PathBuf>)>> {
+    let file = match fs::File::open(rs_file) {
+        Ok(file) => file,
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+    let mut hash = None;
+    let mut imports = vec![];
+    for line in io::BufReader::new(file).lines().skip(1) {
+        let line = try!(line);
+        if hash.is_none() && line.starts_with(HASH_PREFIX) {
+            hash = Some(line[HASH_PREFIX.len()..].to_string());
+        } else if hash.is_some() && line.starts_with(IMPORT_PREFIX) {
+            imports.push(PathBuf::from(&line[IMPORT_PREFIX.len()..]));
+        } else {
+            break;
+        }
+    }
+    Ok(hash.map(|hash| (hash, imports)))
+}
+
+/// Hashes everything the generated code depends on: the `texts` of
+/// the grammar and of the files it imports, the version of LALRPOP,
+/// and the session settings that change what we generate. This uses
+/// 64-bit FNV-1a, which is not cryptographic but, unlike the hashers
+/// in `std`, is guaranteed to give the same result on every platform
+/// and Rust version.
+fn source_hash<'a, I>(session: &Session, texts: I) -> String
+    where I: IntoIterator<Item = &'a [u8]>
+{
+    fn write(hash: &mut u64, bytes: &[u8]) {
+        // prefix each piece with its length, so that moving text
+        // from one piece to the next changes the hash
+        let len = bytes.len() as u64;
+        let len_bytes: Vec<u8> = (0..8).map(|i| (len >> (8 * i)) as u8).collect();
+        for &byte in len_bytes.iter().chain(bytes) {
+            *hash ^= byte as u64;
+            *hash = hash.wrapping_mul(0x100000001b3);
+        }
     }
 
-    #[cfg(not(any(unix,windows)))]
-    fn compare_modification_times(lalrpop_metadata: &fs::Metadata,
-                                  rs_metadata: &fs::Metadata)
-                                  -> bool {
-        true
+    let mut hash = 0xcbf29ce484222325;
+    write(&mut hash, env!("CARGO_PKG_VERSION").as_bytes());
+    write(&mut hash,
+          &[session.emit_comments as u8,
+            session.emit_report as u8,
+            session.emit_dot as u8,
+            session.unit_test as u8,
+            session.deny_warnings as u8]);
+    for text in texts {
+        write(&mut hash, text);
     }
+    format!("{:016x}", hash)
 }
 
 fn make_read_only(rs_file: &Path, ro: bool) -> io::Result<()> {
@@ -161,6 +430,13 @@ fn make_read_only(rs_file: &Path, ro: bool) -> io::Result<()> {
     }
 }
 
+fn write_rerun_directives<W: Write>(out: &mut W, lalrpop_files: &[PathBuf]) -> io::Result<()> {
+    for path in lalrpop_files {
+        try!(writeln!(out, "cargo:rerun-if-changed={}", path.display()));
+    }
+    Ok(())
+}
+
 fn lalrpop_files<P: AsRef<Path>>(root_dir: P) -> io::Result<Vec<PathBuf>> {
     let mut result = vec![];
     for entry in try!(fs::read_dir(root_dir)) {
@@ -181,37 +457,146 @@ fn lalrpop_files<P: AsRef<Path>>(root_dir: P) -> io::Result<Vec<PathBuf>> {
     Ok(result)
 }
 
-fn parse_and_normalize_grammar(session: &Session, file_text: &FileText) -> io::Result<r::Grammar> {
-    let grammar = match parser::parse_grammar(file_text.text()) {
-        Ok(grammar) => grammar,
+/// Parses the grammar in `file_text` and the files it imports. The
+/// text of each imported file is added to `file_text`, and its `use`
+/// items and nonterminals to the grammar, which is returned without
+/// any `import` declarations. A file imported more than once is only
+/// added the first time; the `grammar` declaration of an imported
+/// file is ignored.
+fn parse_grammar(file_text: &mut FileText) -> Result<pt::Grammar, Error> {
+    let mut grammar = try!(parse_file(file_text, 0));
+
+    let mut imports = vec![];
+    grammar.items.retain(|item| match *item {
+        pt::GrammarItem::Import(ref data) => {
+            imports.push(data.clone());
+            false
+        }
+        _ => true,
+    });
+
+    let mut uses: Set<String> =
+        grammar.items.iter()
+                     .filter_map(|item| match *item {
+                         pt::GrammarItem::Use(ref data) => Some(data.clone()),
+                         _ => None,
+                     })
+                     .collect();
+
+    let mut imported = set();
+    if let Ok(path) = fs::canonicalize(file_text.path()) {
+        imported.insert(path);
+    }
+
+    let mut index = 0;
+    while index < imports.len() {
+        let import = imports[index].clone();
+        index += 1;
+
+        // paths are relative to the file containing the `import`
+        let path = {
+            let importer = file_text.file_at(import.span.0).path();
+            importer.parent()
+                    .unwrap_or(Path::new(""))
+                    .join(import.path.to_string())
+        };
+
+        let text = match fs::canonicalize(&path).and_then(|canonical| {
+            if imported.insert(canonical) {
+                read_file(&path).map(Some)
+            } else {
+                Ok(None)
+            }
+        }) {
+            Ok(Some(text)) => text,
+            Ok(None) => continue,
+            Err(err) => {
+                return Err(report_error(file_text,
+                                        import.span,
+                                        &format!("cannot import `{}`: {}", import.path, err)));
+            }
+        };
+
+        let start = file_text.import(path, text);
+        let imported_grammar = try!(parse_file(file_text, start));
+        for item in imported_grammar.items {
+            let span = match item {
+                pt::GrammarItem::Use(data) => {
+                    if uses.insert(data.clone()) {
+                        grammar.items.push(pt::GrammarItem::Use(data));
+                    }
+                    continue;
+                }
+                pt::GrammarItem::Import(data) => {
+                    imports.push(data);
+                    continue;
+                }
+                pt::GrammarItem::Nonterminal(data) => {
+                    grammar.items.push(pt::GrammarItem::Nonterminal(data));
+                    continue;
+                }
+                pt::GrammarItem::ExternToken(data) => data.span,
+                pt::GrammarItem::MatchToken(data) => data.span,
+                pt::GrammarItem::Skip(data) => data.span,
+                pt::GrammarItem::InternToken(_) => imported_grammar.span,
+            };
+            return Err(report_error(file_text,
+                                    span,
+                                    "imported files may only contain `use` items, \
+                                     imports and nonterminals"));
+        }
+    }
+
+    // the prefix must not appear in the imported files either
+    while file_text.imports().any(|import| import.text().contains(&grammar.prefix)) {
+        grammar.prefix.push('_');
+    }
+
+    Ok(grammar)
+}
+
+fn read_file(path: &Path) -> io::Result<String> {
+    let mut text = String::new();
+    try!(try!(fs::File::open(path)).read_to_string(&mut text));
+    Ok(text)
+}
+
+/// Parses the text of `file_text` starting at `start`, which is one
+/// of the files it contains.
+fn parse_file(file_text: &FileText, start: usize) -> Result<pt::Grammar, Error> {
+    let text = file_text.text_at(start);
+    match parser::parse_grammar_at(text, start) {
+        Ok(grammar) => Ok(grammar),
 
         Err(ParseError::InvalidToken { location }) => {
-            let ch = file_text.text()[location..].chars().next().unwrap();
-            report_error(&file_text,
-                         pt::Span(location, location),
-                         &format!("invalid character `{}`", ch));
+            let ch = file_text.text_at(location).chars().next().unwrap();
+            return Err(report_error(&file_text,
+                                    pt::Span(location, location),
+                                    &format!("invalid character `{}`", ch)));
         }
 
-        Err(ParseError::UnrecognizedToken { token: None, expected: _ }) => {
-            let len = file_text.text().len();
-            report_error(&file_text,
-                         pt::Span(len, len),
-                         &format!("unexpected end of file"));
+        Err(ParseError::UnrecognizedToken { token: None, expected }) => {
+            let end = start + text.len();
+            return Err(report_error(&file_text,
+                                    pt::Span(end, end),
+                                    &format!("unexpected end of file{}",
+                                             expected_suffix(&expected))));
         }
 
         Err(ParseError::UnrecognizedToken { token: Some((lo, _, hi)), expected }) => {
-            assert!(expected.is_empty()); // didn't implement this yet :)
-            let text = &file_text.text()[lo..hi];
-            report_error(&file_text,
-                         pt::Span(lo, hi),
-                         &format!("unexpected token: `{}`", text));
+            let text = &file_text.text_at(lo)[..hi - lo];
+            return Err(report_error(&file_text,
+                                    pt::Span(lo, hi),
+                                    &format!("unexpected token: `{}`{}",
+                                             text,
+                                             expected_suffix(&expected))));
         }
 
         Err(ParseError::ExtraToken { token: (lo, _, hi) }) => {
-            let text = &file_text.text()[lo..hi];
-            report_error(&file_text,
-                         pt::Span(lo, hi),
-                         &format!("extra token at end of input: `{}`", text));
+            let text = &file_text.text_at(lo)[..hi - lo];
+            return Err(report_error(&file_text,
+                                    pt::Span(lo, hi),
+                                    &format!("extra token at end of input: `{}`", text)));
         }
 
         Err(ParseError::User { error }) => {
@@ -230,26 +615,119 @@ fn parse_and_normalize_grammar(session: &Session, file_text: &FileText) -> io::R
                 }
             };
 
-            report_error(&file_text,
-                         pt::Span(error.location, error.location + 1),
-                         string)
+            return Err(report_error(&file_text,
+                                    pt::Span(error.location, error.location + 1),
+                                    string));
+        }
+    }
+}
+
+/// Describes the tokens a parse error says were expected, if any, as
+/// a suffix for the error message. These are the names used in
+/// `lrgrammar.lalrpop`, such as `"("` or `Id`.
+fn expected_suffix(expected: &[String]) -> String {
+    match expected.len() {
+        0 => String::new(),
+        1 => format!("; expected {}", expected[0]),
+        n => format!("; expected one of {} or {}",
+                     expected[..n - 1].join(", "),
+                     expected[n - 1]),
+    }
+}
+
+fn normalize_grammar(session: &Session,
+                     file_text: &FileText,
+                     grammar: pt::Grammar)
+                     -> Result<r::Grammar, Error> {
+    let grammar_span = grammar.span;
+    let (grammar, warnings) = match normalize::normalize(session, grammar) {
+        Ok(result) => result,
+        Err(errors) => {
+            let diagnostics =
+                errors.iter()
+                      .map(|error| report_diagnostic(&file_text, error.span, &error.message))
+                      .collect();
+            return Err(Error::Grammar(diagnostics));
         }
     };
 
-    match normalize::normalize(session, grammar) {
-        Ok(grammar) => Ok(grammar),
-        Err(error) => report_error(&file_text, error.span, &error.message),
+    if grammar.start_nonterminals.is_empty() {
+        return Err(report_error(&file_text,
+                                grammar_span,
+                                "no public symbols declared in grammar"));
     }
+
+    try!(report_warnings(file_text, warnings));
+
+    Ok(grammar)
+}
+
+fn report_error(file_text: &FileText, span: pt::Span, message: &str) -> Error {
+    Error::Grammar(vec![report_diagnostic(file_text, span, message)])
 }
 
-fn report_error(file_text: &FileText, span: pt::Span, message: &str) -> ! {
-    println!("{} error: {}", file_text.span_str(span), message);
+/// The diagnostic for an error at `span`. Nothing is printed: that is
+/// left to the callers of `Configuration`, like `process_root` and the
+/// command line, which can print the `rendered` text of the diagnostic.
+fn report_diagnostic(file_text: &FileText, span: pt::Span, message: &str) -> Diagnostic {
+    let heading = format!("{} error: {}", file_text.span_str(span), message);
+    let mut highlight = vec![];
+    file_text.highlight(span, &mut highlight).unwrap();
 
-    let out = io::stderr();
-    let mut out = out.lock();
-    file_text.highlight(span, &mut out).unwrap();
+    Diagnostic {
+        file: file_text.file_at(span.0).path().to_path_buf(),
+        span: file_text.local_span(span),
+        message: message.to_string(),
+        rendered: format!("{}\n{}", heading, String::from_utf8_lossy(&highlight)),
+    }
+}
+
+/// Prints the warnings found by the lint pass. Those that are to be
+/// reported as errors are returned as diagnostics instead; the others
+/// do not stop us from generating the parser.
+fn report_warnings(file_text: &FileText, warnings: Vec<normalize::Warning>) -> Result<(), Error> {
+    let (errors, warnings): (Vec<_>, Vec<_>) =
+        warnings.into_iter().partition(|warning| warning.is_error);
+    if !warnings.is_empty() {
+        let _ = report_messages(warnings.into_iter().map(|warning| warning.message).collect());
+    }
 
-    exit(1);
+    let diagnostics = message_diagnostics(file_text, errors.iter().map(|error| &error.message));
+    if diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::Grammar(diagnostics))
+    }
+}
+
+/// Converts messages, like those describing LR(1) conflicts, into
+/// diagnostics.
+fn message_diagnostics<'m, I>(file_text: &FileText, messages: I) -> Vec<Diagnostic>
+    where I: IntoIterator<Item = &'m Message>
+{
+    messages.into_iter()
+            .map(|message| {
+                let heading = render_content(message.heading());
+                Diagnostic {
+                    file: file_text.file_at(message.span().0).path().to_path_buf(),
+                    span: file_text.local_span(message.span()),
+                    message: heading.split_whitespace().collect::<Vec<_>>().join(" "),
+                    rendered: render_content(message),
+                }
+            })
+            .collect()
+}
+
+/// Renders `content` as plain text, without any colors.
+fn render_content(content: &Content) -> String {
+    let canvas = content.emit_to_canvas(80);
+    let mut out = FakeTerminal::new(vec![]);
+    canvas.write_to(&mut out).unwrap();
+    let text = String::from_utf8(out.into_inner()).unwrap();
+    text.lines()
+        .map(|line| line.trim_right())
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn report_messages(messages: Vec<Message>) -> term::Result<()> {
@@ -269,6 +747,23 @@ fn report_content(content: &Content) -> term::Result<()> {
         ColorConfig::IfTty => atty::is(),
     };
 
+    if log::is_capturing() {
+        let colored = if try_colors { term::TerminfoTerminal::new(vec![]) } else { None };
+        let output = match colored {
+            Some(mut out) => {
+                try!(canvas.write_to(&mut out));
+                out.into_inner()
+            }
+            None => {
+                let mut out = FakeTerminal::new(vec![]);
+                try!(canvas.write_to(&mut out));
+                out.into_inner()
+            }
+        };
+        log::write_captured(&output);
+        return Ok(());
+    }
+
     if try_colors {
         if let Some(mut stdout) = term::stdout() {
             return canvas.write_to(&mut *stdout);
@@ -284,7 +779,20 @@ fn emit_uses<W: Write>(grammar: &r::Grammar, rust: &mut RustWrite<W>) -> io::Res
     rust.write_uses("", grammar)
 }
 
-fn emit_recursive_ascent(session: &Session, grammar: &r::Grammar) -> io::Result<Vec<u8>> {
+/// The error for the conflicts that kept the LR(1) states for a start
+/// symbol from being built, with a diagnostic for each.
+fn report_conflicts(grammar: &r::Grammar, error: &lr1::LR1TableConstructionError) -> Error {
+    let messages = lr1::report_error(grammar, error);
+    Error::Grammar(message_diagnostics(&Tls::file_text(), &messages))
+}
+
+/// Generates the parser for `grammar`. If the session asks for them,
+/// the states built along the way are also described in
+/// `debug_files`, even if there were conflicts.
+fn emit_recursive_ascent(session: &Session,
+                         grammar: &r::Grammar,
+                         debug_files: &mut DebugFiles)
+                         -> Result<Vec<u8>, Error> {
     let mut rust = RustWrite::new(vec![]);
 
     // We generate a module structure like this:
@@ -308,11 +816,17 @@ fn emit_recursive_ascent(session: &Session, grammar: &r::Grammar) -> io::Result<
 
     try!(emit_uses(grammar, &mut rust));
 
-    if grammar.start_nonterminals.is_empty() {
-        println!("Error: no public symbols declared in grammar");
-        exit(1);
+    if let (true, Some(intern_token)) = (session.emit_dot, grammar.intern_token.as_ref()) {
+        let skip = intern_token.skip.iter().flat_map(|skip| skip);
+        let names: Vec<_> = intern_token.literals
+                                        .iter()
+                                        .chain(skip)
+                                        .map(|literal| literal.to_string())
+                                        .collect();
+        debug_files.graphs.push((format!("lexer.dot"), intern_token.dfa.to_dot(&names)));
     }
 
+    let mut start_symbols = vec![];
     for (&user_nt, &start_nt) in &grammar.start_nonterminals {
         // We generate these, so there should always be exactly 1
         // production. Otherwise the LR(1) algorithm doesn't know
@@ -326,15 +840,25 @@ fn emit_recursive_ascent(session: &Session, grammar: &r::Grammar) -> io::Result<
 
         let _lr1_tls = lr1::Lr1Tls::install(grammar.terminals.clone());
 
-        let states = match lr1::build_states(&grammar, start_nt) {
+        let result = lr1::build_states(&grammar, start_nt);
+        if session.emit_report {
+            try!(lr1::report::write_report(&mut debug_files.report, grammar, user_nt, &result));
+        }
+        if session.emit_dot {
+            let states = match result {
+                Ok(ref states) => states,
+                Err(ref error) => &error.states,
+            };
+            let graph = lr1::StateGraph::new(states).to_dot(states, start_nt);
+            debug_files.graphs.push((format!("{}.dot", user_nt), graph));
+        }
+        let states = match result {
             Ok(states) => states,
-            Err(error) => {
-                let messages = lr1::report_error(&grammar, &error);
-                let _ = report_messages(messages);
-                exit(1) // FIXME -- propagate up instead of calling `exit`
-            }
+            Err(error) => return Err(report_conflicts(grammar, &error)),
         };
 
+        start_symbols.push((user_nt, lr1::expected_terminals(grammar, &states[0])));
+
         match grammar.algorithm.codegen {
             r::LrCodeGeneration::RecursiveAscent =>
                 try!(lr1::codegen::ascent::compile(&grammar,
@@ -360,12 +884,31 @@ fn emit_recursive_ascent(session: &Session, grammar: &r::Grammar) -> io::Result<
               grammar.prefix,
               start_nt,
               user_nt);
+
+        // the push parser takes the tokens of an external tokenizer;
+        // the generated one needs the whole input up front, so there
+        // would be nothing to push
+        if grammar.algorithm.codegen != r::LrCodeGeneration::RecursiveAscent {
+            if grammar.intern_token.is_none() {
+                rust!(rust,
+                      "pub use self::{}parse{}::{}Parser;",
+                      grammar.prefix,
+                      start_nt,
+                      user_nt);
+            } else {
+                rust!(rust,
+                      "// no `{}Parser`: push parsers need an `extern` token declaration",
+                      user_nt);
+            }
+        }
     }
 
     if let Some(ref intern_token) = grammar.intern_token {
         try!(intern_token::compile(&grammar, intern_token, &mut rust));
     }
 
+    try!(emit_metadata(grammar, &start_symbols, &mut rust));
+
     try!(action::emit_action_code(grammar, &mut rust));
 
     try!(emit_to_triple_trait(grammar, &mut rust));
@@ -373,6 +916,77 @@ fn emit_recursive_ascent(session: &Session, grammar: &r::Grammar) -> io::Result<
     Ok(rust.into_inner())
 }
 
+fn emit_metadata<W: Write>(grammar: &r::Grammar,
+                           start_symbols: &[(r::NonterminalString, Vec<r::TerminalString>)],
+                           rust: &mut RustWrite<W>)
+                           -> io::Result<()> {
+    // the name is fixed, rather than built from the grammar's
+    // prefix, so that users can rely on it; grammars should not
+    // define or import a `__metadata` of their own
+    rust!(rust, "");
+    rust!(rust, "/// Describes the grammar this module was generated from, for the");
+    rust!(rust, "/// benefit of tools like syntax highlighters and error renderers.");
+    rust!(rust, "pub mod __metadata {{");
+    rust!(rust, "#![allow(dead_code)]");
+    rust!(rust, "");
+
+    // `error` is always the last terminal, and is not a real token
+    let terminals = &grammar.terminals.all[..grammar.terminals.all.len() - 1];
+    rust!(rust, "/// The terminals of the grammar, as written in the grammar: like");
+    rust!(rust, "/// `\"+\"`, `r#\"[0-9]+\"#` or `Num`. These are also the names used in");
+    rust!(rust, "/// the `expected` list of a `ParseError`.");
+    rust!(rust, "pub const TERMINALS: &'static [&'static str] = &[");
+    for terminal in terminals {
+        // Three # should hopefully be enough to prevent any reasonable terminal from escaping the literal
+        rust!(rust, "r###\"{}\"###,", terminal);
+    }
+    rust!(rust, "];");
+    rust!(rust, "");
+
+    rust!(rust, "/// A terminal, identified by its index in `TERMINALS`.");
+    rust!(rust, "#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]");
+    rust!(rust, "pub struct Token(pub usize);");
+    rust!(rust, "");
+    rust!(rust, "impl Token {{");
+    rust!(rust, "/// The terminal as written in the grammar.");
+    rust!(rust, "pub fn name(self) -> &'static str {{");
+    rust!(rust, "TERMINALS[self.0]");
+    rust!(rust, "}}");
+    rust!(rust, "}}");
+    rust!(rust, "");
+
+    rust!(rust, "/// A public nonterminal, for which there is a `parse_` function.");
+    rust!(rust, "#[derive(Copy, Clone, Debug)]");
+    rust!(rust, "pub struct StartSymbol {{");
+    rust!(rust, "/// The name of the nonterminal, like `Expr`.");
+    rust!(rust, "pub name: &'static str,");
+    rust!(rust, "/// The type of the value it produces, like `i32`.");
+    rust!(rust, "pub ty: &'static str,");
+    rust!(rust, "/// The terminals that the parser accepts as the first token.");
+    rust!(rust, "pub expected: &'static [Token],");
+    rust!(rust, "}}");
+    rust!(rust, "");
+
+    rust!(rust, "/// The public nonterminals of the grammar.");
+    rust!(rust, "pub const START_SYMBOLS: &'static [StartSymbol] = &[");
+    for &(nonterminal, ref expected) in start_symbols {
+        let expected: Vec<_> = expected.iter()
+                                       .map(|terminal| format!("Token({})", grammar.terminals.bits[terminal]))
+                                       .collect();
+        rust!(rust, "StartSymbol {{");
+        rust!(rust, "name: \"{}\",", nonterminal);
+        rust!(rust, "ty: r###\"{}\"###,", grammar.types.nonterminal_type(nonterminal));
+        rust!(rust, "expected: &[{}],", expected.join(", "));
+        rust!(rust, "}},");
+    }
+    rust!(rust, "];");
+
+    rust!(rust, "}}");
+    Ok(())
+}
+
+/// Whether one of the `use` items of `grammar` brings `name` into
+/// scope, as `a::name`, `a::{b, name}` or `a::b as name` do.
 fn emit_to_triple_trait<W: Write>(grammar: &r::Grammar, rust: &mut RustWrite<W>) -> io::Result<()> {
     #![allow(non_snake_case)]
 
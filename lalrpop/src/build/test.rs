@@ -0,0 +1,612 @@
+use api::Configuration;
+use log;
+use session::Session;
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use super::{expected_suffix, header, interpret_file, lalrpop_files, process_dir, process_file,
+            process_str, source_hash, write_rerun_directives, Diagnostic, Error,
+            InterpretError};
+
+fn write_grammar(name: &str, grammar: &str) -> PathBuf {
+    let dir = env::temp_dir().join("lalrpop-build-test");
+    fs::create_dir_all(&dir).unwrap();
+    let lalrpop_file = dir.join(name).with_extension("lalrpop");
+    File::create(&lalrpop_file).unwrap().write_all(grammar.as_bytes()).unwrap();
+    lalrpop_file
+}
+
+fn diagnostics(lalrpop_file: &Path) -> Vec<Diagnostic> {
+    let mut session = Session::test();
+    session.force_build = true;
+    match process_file(Rc::new(session), lalrpop_file) {
+        Err(Error::Grammar(diagnostics)) => diagnostics,
+        r => panic!("expected grammar errors, got {:?}", r),
+    }
+}
+
+fn check_diagnostics(name: &str, grammar: &str, expected: Vec<(&str, &str)>) {
+    let lalrpop_file = write_grammar(name, grammar);
+    let diagnostics = diagnostics(&lalrpop_file);
+
+    let actual: Vec<_> =
+        diagnostics.iter()
+                   .map(|d| {
+                       assert_eq!(d.file, lalrpop_file);
+                       assert!(d.rendered.contains(&d.message));
+                       (&grammar[d.span.0..d.span.1], &d.message[..])
+                   })
+                   .collect();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn parse_error() {
+    check_diagnostics(
+        "parse_error",
+        r#"grammar; pub X = "a" }"#,
+        vec![("}", r#"unexpected token: `}`; expected ";""#)]);
+}
+
+#[test]
+fn parse_error_expected_tokens() {
+    check_diagnostics(
+        "parse_error_expected_tokens",
+        r#"grammar; pub X: = "a";"#,
+        vec![("=",
+              r##"unexpected token: `=`; expected one of "#", "&", "(", "::", "Id" or "MacroId""##)]);
+    check_diagnostics(
+        "parse_error_expected_string",
+        r#"grammar; import ;"#,
+        vec![(";",
+              r#"unexpected token: `;`; expected one of ":", "=", "StringLiteral" or "{""#)]);
+}
+
+#[test]
+fn parse_error_misspelled_keyword() {
+    check_diagnostics(
+        "parse_error_misspelled_keyword",
+        r#"grammar; skp { "x" }"#,
+        vec![("skp", r#"unexpected token: `skp`; expected one of "match" or "skip""#)]);
+    check_diagnostics(
+        "parse_error_else_without_match",
+        r#"grammar; skip { "x" } else { "y" }"#,
+        vec![("else", r#"unexpected token: `else`; expected one of "match" or "skip""#)]);
+}
+
+#[test]
+fn parse_error_skip_items() {
+    check_diagnostics(
+        "parse_error_skip_catch_all",
+        r#"grammar; skip { "x", _ }"#,
+        vec![("_", r#"unexpected token: `_`; expected one of "RegexLiteral" or "StringLiteral""#)]);
+    check_diagnostics(
+        "parse_error_skip_mapping",
+        r#"grammar; skip { "x" => "y" }"#,
+        vec![(r#"=> "y" "#, r#"unexpected token: `=> "y" `; expected one of "," or "}""#)]);
+}
+
+#[test]
+fn errors_are_not_printed() {
+    // embedders get the diagnostics back, and print them if they like
+    for &(name, grammar) in &[("errors_not_printed_validation", r#"grammar; pub X = Y;"#),
+                              ("errors_not_printed_conflict", r#"grammar; pub E = { E "+" E, "x" };"#)] {
+        let lalrpop_file = write_grammar(name, grammar);
+        let mut session = Session::test();
+        session.log.set_level(log::Level::Taciturn);
+        session.force_build = true;
+        let (result, output) = log::capture(|| process_file(Rc::new(session), &lalrpop_file));
+        match result {
+            Err(Error::Grammar(ref diagnostics)) => assert_eq!(diagnostics.len(), 1),
+            r => panic!("expected grammar errors, got {:?}", r),
+        }
+        assert_eq!(String::from_utf8(output).unwrap(), "");
+    }
+}
+
+#[test]
+fn all_validation_errors() {
+    check_diagnostics(
+        "all_validation_errors",
+        r#"grammar; pub X = <a:"a">; #[foo] Y = "b";"#,
+        vec![(r#""a""#, r#"named symbols (like `"a"`) require a custom action"#),
+             ("foo", "unrecognized annotation `foo`")]);
+}
+
+#[test]
+fn no_public_symbols() {
+    check_diagnostics(
+        "no_public_symbols",
+        r#"grammar; X = "a";"#,
+        vec![("grammar", "no public symbols declared in grammar")]);
+}
+
+#[test]
+fn conflict() {
+    check_diagnostics(
+        "conflict",
+        r#"grammar; pub E = { E "+" E, "x" };"#,
+        vec![(r#"E "+" E"#, "Ambiguous grammar detected")]);
+}
+
+#[test]
+fn import_error_in_imported_file() {
+    let common = r#"grammar; Atom = { <a:"x">, "(" Atom ")" };"#;
+    let common_file = write_grammar("import_error_common", common);
+    let main_file = write_grammar(
+        "import_error_main",
+        r#"grammar; import "import_error_common.lalrpop"; pub X = Atom;"#);
+
+    let diagnostics = diagnostics(&main_file);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].file, common_file);
+    assert_eq!(&common[diagnostics[0].span.0..diagnostics[0].span.1], r#""x""#);
+    assert!(diagnostics[0].rendered.contains("import_error_common.lalrpop:1:"));
+}
+
+#[test]
+fn import_missing_file() {
+    let grammar = r#"grammar; import "import_missing_nowhere.lalrpop"; pub X = "x";"#;
+    let lalrpop_file = write_grammar("import_missing", grammar);
+
+    let diagnostics = diagnostics(&lalrpop_file);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].file, lalrpop_file);
+    assert_eq!(&grammar[diagnostics[0].span.0..diagnostics[0].span.1],
+               r#"import "import_missing_nowhere.lalrpop""#);
+    assert!(diagnostics[0].message.starts_with("cannot import `import_missing_nowhere.lalrpop`"));
+}
+
+#[test]
+fn import_token_declaration() {
+    let common = r#"grammar; extern { enum Tok { "x" => Tok::X } }"#;
+    write_grammar("import_token_common", common);
+    let lalrpop_file = write_grammar(
+        "import_token_main",
+        r#"grammar; import "import_token_common.lalrpop"; pub X = "x";"#);
+
+    let diagnostics = diagnostics(&lalrpop_file);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].message,
+               "imported files may only contain `use` items, imports and nonterminals");
+}
+
+#[test]
+fn import_cycle() {
+    // each file is only imported once, so the cycle is harmless and
+    // the only error is the conflict between the two files' rules
+    let a_file = write_grammar(
+        "import_cycle_a",
+        r#"grammar; import "import_cycle_b.lalrpop"; pub X = { Y, "x" };"#);
+    write_grammar("import_cycle_b",
+                  r#"grammar; import "import_cycle_a.lalrpop"; Y = "x";"#);
+
+    let diagnostics = diagnostics(&a_file);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].file, a_file);
+    assert_eq!(diagnostics[0].message, "Conflict detected");
+}
+
+fn report(name: &str, grammar: &str) -> String {
+    let lalrpop_file = write_grammar(name, grammar);
+    let mut session = Session::test();
+    session.force_build = true;
+    session.emit_report = true;
+    let _ = process_file(Rc::new(session), &lalrpop_file);
+    let mut report = String::new();
+    File::open(lalrpop_file.with_extension("lalrpop.txt"))
+        .unwrap()
+        .read_to_string(&mut report)
+        .unwrap();
+    report
+}
+
+#[test]
+fn report_states() {
+    let report = report("report_states", r#"grammar; pub X = { "(" X ")", "x" };"#);
+    assert!(report.starts_with("Start symbol `X`: "));
+    assert!(report.contains(r#"X = "(" (*) X ")" [")"]"#));
+    assert!(report.contains(r#"    "x" -> shift S"#));
+    assert!(report.contains("    X -> goto S"));
+    assert!(report.contains("No conflicts."));
+}
+
+#[test]
+fn report_conflicts() {
+    let report = report("report_conflicts", r#"grammar; pub E = { E "+" E, "x" };"#);
+    assert!(report.contains("Conflicts (1):"));
+    assert!(report.contains(r#"on "+": reduce `E = E, "+", E"#));
+    assert!(report.contains("ambiguous grammar"));
+}
+
+#[test]
+fn dot_files() {
+    let lalrpop_file = write_grammar("dot_files", r#"grammar; pub X = { "(" X ")", "\"" };"#);
+    let mut session = Session::test();
+    session.force_build = true;
+    session.emit_dot = true;
+    process_file(Rc::new(session), &lalrpop_file).unwrap();
+
+    let read = |extension: &str| {
+        let mut dot = String::new();
+        File::open(lalrpop_file.with_extension(extension))
+            .unwrap()
+            .read_to_string(&mut dot)
+            .unwrap();
+        dot
+    };
+
+    let states = read("X.dot");
+    assert!(states.starts_with("digraph {"));
+    assert!(states.contains(r#"[label="S0\l__X = (*) X [EOF]\l"]"#));
+    assert!(states.contains(r#"X = \"(\" (*) X \")\" [\")\"]\l"#));
+    // backslashes are escaped, and so are the quotes of the `Debug` output
+    assert!(states.contains(r#"0 -> 3 [label="\"\\\\\\\"\""]"#));
+
+    let lexer = read("lexer.dot");
+    assert!(lexer.starts_with("digraph {"));
+    assert!(lexer.contains(r#"accepts \"(\""#));
+    assert!(lexer.contains(r#"accepts \"\\\\\\\"\""#));
+    assert!(lexer.contains(r#"0 -> 3 [label="\\"]"#));
+}
+
+#[test]
+fn unchanged_output_is_not_rewritten() {
+    let lalrpop_file = write_grammar("unchanged_output", r#"grammar; pub X = "x";"#);
+    let rs_file = lalrpop_file.with_extension("rs");
+    let process = || {
+        let mut session = Session::test();
+        session.force_build = true;
+        process_file(Rc::new(session), &lalrpop_file).unwrap();
+        fs::metadata(&rs_file).unwrap()
+    };
+
+    let first = process();
+    assert!(first.permissions().readonly());
+    let second = process();
+    assert_eq!(first.modified().unwrap(), second.modified().unwrap());
+
+    // a changed grammar replaces the read-only file, leaving no
+    // temporary file behind
+    write_grammar("unchanged_output", r#"grammar; pub X = "y";"#);
+    process();
+    let mut text = String::new();
+    File::open(&rs_file).unwrap().read_to_string(&mut text).unwrap();
+    assert!(text.contains(r#""y""#));
+    let leftovers: Vec<_> =
+        fs::read_dir(rs_file.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .filter(|name| name.starts_with("unchanged_output.rs."))
+            .collect();
+    assert!(leftovers.is_empty(), "temporary files left behind: {:?}", leftovers);
+}
+
+#[test]
+fn rebuild_decided_by_source_hash() {
+    let lalrpop_file = write_grammar("source_hash", r#"grammar; pub X = "x";"#);
+    let rs_file = lalrpop_file.with_extension("rs");
+    let process = |force_build: bool| {
+        let mut session = Session::test();
+        session.force_build = force_build;
+        process_file(Rc::new(session), &lalrpop_file).unwrap();
+        let mut text = String::new();
+        File::open(&rs_file).unwrap().read_to_string(&mut text).unwrap();
+        text
+    };
+    let mark = |text: &str| {
+        let mut permissions = fs::metadata(&rs_file).unwrap().permissions();
+        permissions.set_readonly(false);
+        fs::set_permissions(&rs_file, permissions).unwrap();
+        File::create(&rs_file).unwrap().write_all(format!("{}// marked\n", text).as_bytes()).unwrap();
+    };
+
+    let text = process(true);
+    assert!(text.starts_with("// auto-generated by lalrpop"));
+
+    // rewriting the grammar with the same contents, which would bump
+    // its modification time, does not trigger a rebuild
+    mark(&text);
+    write_grammar("source_hash", r#"grammar; pub X = "x";"#);
+    assert!(process(false).ends_with("// marked\n"));
+
+    // but changing it does
+    write_grammar("source_hash", r#"grammar; pub X = "y";"#);
+    let text = process(false);
+    assert!(!text.ends_with("// marked\n"));
+    assert!(text.contains(r#""y""#));
+
+    // and so does forcing the build
+    mark(&text);
+    assert!(!process(true).ends_with("// marked\n"));
+}
+
+#[test]
+fn rebuild_decided_by_imports() {
+    let lalrpop_file = write_grammar("hash_importer", r#"grammar; import "hash_imported.lalrpop";"#);
+    let imported_file = write_grammar("hash_imported", r#"grammar; pub X = "x";"#);
+    let rs_file = lalrpop_file.with_extension("rs");
+    let process = || {
+        process_file(Rc::new(Session::test()), &lalrpop_file).unwrap();
+        let mut text = String::new();
+        File::open(&rs_file).unwrap().read_to_string(&mut text).unwrap();
+        text
+    };
+
+    let text = process();
+    assert!(text.contains(&format!("\n// imports: {}\n", imported_file.display())));
+    assert!(text.contains(r#""x""#));
+
+    // changing only the imported file triggers a rebuild
+    write_grammar("hash_imported", r#"grammar; pub X = "y";"#);
+    assert!(process().contains(r#""y""#));
+}
+
+#[test]
+fn up_to_date_without_parsing() {
+    // the check only hashes the text, so a matching header means
+    // that the grammar is not even parsed
+    let text = "not a grammar";
+    let lalrpop_file = write_grammar("up_to_date", text);
+    let rs_file = lalrpop_file.with_extension("rs");
+    let session = Session::test();
+    let _ = fs::remove_file(&rs_file);
+    let hash = source_hash(&session, Some(text.as_bytes()));
+    File::create(&rs_file).unwrap().write_all(header(&hash, &[]).as_bytes()).unwrap();
+    process_file(Rc::new(session), &lalrpop_file).unwrap();
+
+    // otherwise it is
+    File::create(&rs_file).unwrap().write_all(header("0", &[]).as_bytes()).unwrap();
+    assert!(process_file(Rc::new(Session::test()), &lalrpop_file).is_err());
+}
+
+#[test]
+fn rerun_directives() {
+    let dir = env::temp_dir().join("lalrpop-build-test").join("rerun_directives");
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    File::create(dir.join("a.lalrpop")).unwrap();
+    File::create(dir.join("nested").join("b.lalrpop")).unwrap();
+    File::create(dir.join("nested").join("b.rs")).unwrap();
+
+    let mut files = lalrpop_files(&dir).unwrap();
+    files.sort();
+    let mut out = vec![];
+    write_rerun_directives(&mut out, &files).unwrap();
+    let expected: String =
+        [dir.join("a.lalrpop"), dir.join("nested").join("b.lalrpop")]
+            .iter()
+            .map(|path| format!("cargo:rerun-if-changed={}\n", path.display()))
+            .collect();
+    assert_eq!(String::from_utf8(out).unwrap(), expected);
+}
+
+#[test]
+fn process_dir_in_parallel() {
+    let dir = env::temp_dir().join("lalrpop-build-test").join("parallel");
+    fs::create_dir_all(&dir).unwrap();
+    let grammars = [("a", r#"grammar; pub X = "a";"#),
+                    ("b", r#"grammar; pub X = "b" }"#),
+                    ("c", r#"grammar; pub X = "c";"#),
+                    ("d", r#"grammar; X = "d";"#),
+                    ("e", r#"grammar; pub X = { "e", "e" };"#)];
+    for &(name, grammar) in &grammars {
+        File::create(dir.join(name).with_extension("lalrpop"))
+            .unwrap()
+            .write_all(grammar.as_bytes())
+            .unwrap();
+    }
+
+    let mut session = Session::test();
+    session.force_build = true;
+    session.jobs = 3;
+    let mut failed: Vec<_> = match process_dir(Rc::new(session), &dir) {
+        Err(Error::Grammar(diagnostics)) => {
+            diagnostics.iter()
+                       .map(|d| d.file.file_stem().unwrap().to_string_lossy().into_owned())
+                       .collect()
+        }
+        r => panic!("expected grammar errors, got {:?}", r),
+    };
+    failed.sort();
+    assert_eq!(failed, vec!["b", "d", "e"]);
+    assert!(dir.join("a.rs").exists());
+    assert!(dir.join("c.rs").exists());
+}
+
+#[test]
+fn parallel_output_matches_sequential() {
+    let dir = env::temp_dir().join("lalrpop-build-test").join("parallel_output");
+    fs::create_dir_all(&dir).unwrap();
+    let grammars = [("a", r#"grammar; pub X = { "a" X, "b" };"#),
+                    ("b", r#"grammar; pub Y = Comma<"y">; Comma<T> = (T ",")* T?;"#),
+                    ("c", r#"grammar; match { "if" } else { r"[a-z]+" } pub Z = "if" r"[a-z]+";"#),
+                    ("d", r#"grammar; pub X = "b" Y; Y = { "a", Y "a" };"#),
+                    ("e", r#"grammar; pub W: u32 = <r"[0-9]+"> => 0;"#)];
+    for &(name, grammar) in &grammars {
+        File::create(dir.join(name).with_extension("lalrpop"))
+            .unwrap()
+            .write_all(grammar.as_bytes())
+            .unwrap();
+    }
+
+    let process_with_jobs = |jobs: usize| {
+        let out_dir = dir.join(format!("jobs{}", jobs));
+        Configuration::new()
+            .force_build(true)
+            .log_quiet()
+            .set_jobs(jobs)
+            .set_in_dir(dir.clone())
+            .set_out_dir(out_dir.clone())
+            .process_dir(&dir)
+            .unwrap();
+        out_dir
+    };
+    let sequential = process_with_jobs(1);
+    let parallel = process_with_jobs(4);
+    for &(name, _) in &grammars {
+        let read = |dir: &Path| {
+            let mut text = String::new();
+            File::open(dir.join(name).with_extension("rs"))
+                .unwrap()
+                .read_to_string(&mut text)
+                .unwrap();
+            text
+        };
+        assert!(read(&sequential) == read(&parallel),
+                "`{}` differs when processed in parallel", name);
+    }
+}
+
+#[test]
+fn denied_warnings() {
+    let grammar = r#"grammar; pub X = "x"; Y = "y";"#;
+    let lalrpop_file = write_grammar("denied_warnings", grammar);
+    let mut session = Session::test();
+    session.force_build = true;
+    process_file(Rc::new(session.clone()), &lalrpop_file).unwrap();
+
+    session.deny_warnings = true;
+    match process_file(Rc::new(session), &lalrpop_file) {
+        Err(Error::Grammar(diagnostics)) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(&grammar[diagnostics[0].span.0..diagnostics[0].span.1], "Y");
+            assert_eq!(diagnostics[0].message, "error: nonterminal `Y` is never used");
+        }
+        r => panic!("expected grammar errors, got {:?}", r),
+    }
+}
+
+#[test]
+fn process_grammar_str() {
+    let code = process_str(Rc::new(Session::test()), r#"grammar; pub X = "x";"#).unwrap();
+    assert!(code.contains("pub fn parse_X"));
+
+    let grammar = r#"grammar; pub X = Y;"#;
+    match process_str(Rc::new(Session::test()), grammar) {
+        Err(Error::Grammar(diagnostics)) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].file, PathBuf::from("<input>"));
+            assert_eq!(&grammar[diagnostics[0].span.0..diagnostics[0].span.1], "Y");
+        }
+        r => panic!("expected grammar errors, got {:?}", r),
+    }
+
+    let grammar = r#"grammar; import "other.lalrpop"; pub X = "x";"#;
+    match process_str(Rc::new(Session::test()), grammar) {
+        Err(Error::Grammar(diagnostics)) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].message,
+                       "grammars processed from a string cannot import files");
+        }
+        r => panic!("expected grammar errors, got {:?}", r),
+    }
+}
+
+#[test]
+fn metadata_module_name() {
+    // the name does not depend on the grammar's prefix, which is
+    // `___` here because the grammar mentions `__`
+    let code = process_str(Rc::new(Session::test()), r#"grammar; pub X = "__";"#).unwrap();
+    assert!(code.contains("pub mod __metadata {"));
+    assert!(code.contains("___ACTION"));
+    assert!(!code.contains("___metadata"));
+}
+
+#[test]
+fn push_parser_needs_extern_tokens() {
+    let code = process_str(Rc::new(Session::test()), r#"grammar; pub X = "x";"#).unwrap();
+    assert!(!code.contains("pub use self::__parse__X::XParser;"));
+    assert!(code.contains("// no `XParser`: push parsers need an `extern` token declaration"));
+
+    let code = process_str(Rc::new(Session::test()), r#"
+        grammar;
+        extern { enum Tok { "x" => Tok::X } }
+        pub X = "x";
+    "#).unwrap();
+    assert!(code.contains("pub use self::__parse__X::XParser;"));
+}
+
+#[test]
+fn interpret_terminals() {
+    let lalrpop_file = write_grammar("interpret", r#"
+        grammar;
+        pub Expr: () = { Expr "-" Term => (), Term => () };
+        Term: () = { "N" => (), "(" Expr ")" => () };
+    "#);
+    let interpret = |start: &str, terminals: &str| {
+        interpret_file(Rc::new(Session::test()), &lalrpop_file, start, terminals).unwrap()
+    };
+
+    assert_eq!(interpret("Expr", r#"N - ( "N" )"#).unwrap().to_string(),
+               r#"[Expr: [Expr: [Term: "N"]], "-", [Term: "(", [Expr: [Term: "N"]], ")"]]"#);
+
+    // the start symbol need not be public
+    assert_eq!(interpret("Term", "N").unwrap().to_string(), r#"[Term: "N"]"#);
+
+    let error = interpret("Expr", "N - -").unwrap_err();
+    assert_eq!(error, InterpretError {
+        position: 2,
+        found: Some("-".to_string()),
+        expected: vec![r#""(""#.to_string(), r#""N""#.to_string()],
+    });
+    assert_eq!(error.to_string(),
+               r#"unexpected terminal `-` at position 2; expected one of "(" or "N""#);
+
+    let error = interpret("Expr", "( N").unwrap_err();
+    assert_eq!((error.position, error.found), (2, None));
+    assert_eq!(error.expected, vec![r#"")""#.to_string(), r#""-""#.to_string()]);
+
+    // terminals that are not in the grammar are reported where the
+    // parse gets to them
+    let error = interpret("Expr", "N x").unwrap_err();
+    assert_eq!((error.position, error.found), (1, Some("x".to_string())));
+    let error = interpret("Expr", ") x").unwrap_err();
+    assert_eq!((error.position, error.found), (0, Some(")".to_string())));
+
+    match interpret_file(Rc::new(Session::test()), &lalrpop_file, "Factor", "N") {
+        Err(Error::Grammar(diagnostics)) => {
+            assert_eq!(diagnostics[0].message, "no nonterminal `Factor` to start from");
+        }
+        r => panic!("expected grammar errors, got {:?}", r),
+    }
+}
+
+#[test]
+fn interpret_inline_nonterminal() {
+    let lalrpop_file = write_grammar("interpret_inline", r#"
+        grammar;
+        pub Expr: () = { Expr "-" Term => (), Term => () };
+        #[inline] Term: () = { "N" => (), "(" Expr ")" => () };
+    "#);
+    match interpret_file(Rc::new(Session::test()), &lalrpop_file, "Term", "N") {
+        Err(Error::Grammar(diagnostics)) => {
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].message, "cannot interpret inline nonterminal `Term`");
+        }
+        r => panic!("expected grammar errors, got {:?}", r),
+    }
+}
+
+#[test]
+fn contextual_keywords_as_names() {
+    let grammar = r#"
+        grammar;
+        pub skip: () = <import:match> => ();
+        match = "else";
+    "#;
+    process_str(Rc::new(Session::test()), grammar).unwrap();
+}
+
+#[test]
+fn expected_tokens() {
+    let expected = |names: &[&str]| {
+        expected_suffix(&names.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+    };
+    assert_eq!(expected(&[]), "");
+    assert_eq!(expected(&["\";\""]), "; expected \";\"");
+    assert_eq!(expected(&["\"(\"", "\"=\"", "Id"]),
+               "; expected one of \"(\", \"=\" or Id");
+}
+
@@ -1,13 +1,22 @@
 use grammar::parse_tree as pt;
 use std::fmt::{Display, Formatter, Error};
 use std::fs::File;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::slice;
 use std::io::{self, Read, Write};
 
 pub struct FileText {
     path: PathBuf,
     input_str: String,
     newlines: Vec<usize>,
+
+    // position of `input_str` within the positions shared by a grammar
+    // and its imports; always 0 for the file being processed
+    start: usize,
+
+    // the files imported by the grammar, directly or indirectly, each
+    // with its own range of positions following that of the grammar
+    imports: Vec<FileText>,
 }
 
 impl FileText {
@@ -30,7 +39,13 @@ impl FileText {
                    .collect()
         };
 
-        FileText { path: path, input_str: input_str, newlines: newline_indices }
+        FileText {
+            path: path,
+            input_str: input_str,
+            newlines: newline_indices,
+            start: 0,
+            imports: vec![],
+        }
     }
 
     #[cfg(test)]
@@ -38,19 +53,68 @@ impl FileText {
         Self::new(PathBuf::from("test.lalrpop"), String::from(""))
     }
 
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
     pub fn text(&self) -> &String {
         &self.input_str
     }
 
+    /// Adds the text of an imported file, returning the position at
+    /// which it starts. Spans within the imported file must be offset
+    /// by this amount, so that `span_str` and friends can tell which
+    /// file they refer to.
+    pub fn import(&mut self, path: PathBuf, input_str: String) -> usize {
+        let start = self.imports.last().unwrap_or(self).end() + 1;
+        let mut file_text = FileText::new(path, input_str);
+        file_text.start = start;
+        self.imports.push(file_text);
+        start
+    }
+
+    /// The files imported by this one, directly or indirectly.
+    pub fn imports(&self) -> slice::Iter<FileText> {
+        self.imports.iter()
+    }
+
+    /// The position just past the end of this file.
+    pub fn end(&self) -> usize {
+        self.start + self.input_str.len()
+    }
+
+    /// The file, either this one or one of its imports, containing
+    /// `pos`.
+    pub fn file_at(&self, pos: usize) -> &FileText {
+        self.imports.iter()
+                    .rev()
+                    .find(|file_text| file_text.start <= pos)
+                    .unwrap_or(self)
+    }
+
+    /// Converts `span` into positions within the file containing it.
+    pub fn local_span(&self, span: pt::Span) -> pt::Span {
+        let start = self.file_at(span.0).start;
+        pt::Span(span.0 - start, span.1 - start)
+    }
+
+    /// The text of the file containing `pos`, starting at `pos`.
+    pub fn text_at(&self, pos: usize) -> &str {
+        let file_text = self.file_at(pos);
+        &file_text.input_str[pos - file_text.start..]
+    }
+
     pub fn span_str(&self, span: pt::Span) -> String {
-        let (start_line, start_col) = self.line_col(span.0);
-        let (end_line, end_col) = self.line_col(span.1);
+        let file_text = self.file_at(span.0);
+        let (start_line, start_col) = file_text.line_col(span.0);
+        let (end_line, end_col) = file_text.line_col(span.1);
         format!("{}:{}:{}: {}:{}",
-                self.path.display(),
+                file_text.path.display(),
                 start_line+1, start_col+1, end_line+1, end_col)
     }
 
     fn line_col(&self, pos: usize) -> (usize, usize) {
+        let pos = pos - self.start;
         let num_lines = self.newlines.len();
         let line =
             (0..num_lines)
@@ -80,6 +144,11 @@ impl FileText {
     }
 
     pub fn highlight(&self, span: pt::Span, out: &mut Write) -> io::Result<()> {
+        let file_text = self.file_at(span.0);
+        if file_text.start != self.start {
+            return file_text.highlight(span, out);
+        }
+
         let (start_line, start_col) = self.line_col(span.0);
         let (end_line, end_col) = self.line_col(span.1);
 
@@ -1,15 +1,10 @@
 //! Generate valid parse trees.
 
 use grammar::repr::*;
+use lr1::ParseTree;
 use rand::{self, Rng};
 use std::iter::Iterator;
 
-#[derive(PartialEq, Eq)]
-pub enum ParseTree {
-    Nonterminal(NonterminalString, Vec<ParseTree>),
-    Terminal(TerminalString),
-}
-
 pub fn random_parse_tree(grammar: &Grammar, symbol: NonterminalString) -> ParseTree {
     let mut gen = Generator { grammar: grammar, rng: rand::thread_rng(), depth: 0 };
     loop {
@@ -16,6 +16,9 @@ pub const INLINE: &'static str = "inline";
 /// Annotation to request LALR.
 pub const LALR: &'static str = "LALR";
 
+/// Annotation to request the lane table LR(1) construction.
+pub const LANE_TABLE: &'static str = "lane_table";
+
 /// Annotation to request recursive-ascent-style code generation.
 pub const TABLE_DRIVEN: &'static str = "table_driven";
 
@@ -25,3 +28,42 @@ pub const RECURSIVE_ASCENT: &'static str = "recursive_ascent";
 /// Annotation to request test-all-style code generation.
 pub const TEST_ALL: &'static str = "test_all";
 
+/// Annotation to choose how the parser recovers from errors, as in
+/// `#[recovery(strategy="repair")]`.
+pub const RECOVERY: &'static str = "recovery";
+
+/// Argument of the `recovery` annotation.
+pub const STRATEGY: &'static str = "strategy";
+
+/// Annotation to cap the number of times the parser recovers from an
+/// error in one parse, as in `#[recovery_limit(max="10")]`.
+pub const RECOVERY_LIMIT: &'static str = "recovery_limit";
+
+/// Argument of the `recovery_limit` annotation.
+pub const MAX: &'static str = "max";
+
+/// Annotation to silence a lint for the whole grammar, as in
+/// `#[allow(lint="unused_terminals")]`.
+pub const ALLOW: &'static str = "allow";
+
+/// Annotation to report a lint as an error rather than a warning, as
+/// in `#[deny(lint="unreachable_nonterminals")]`.
+pub const DENY: &'static str = "deny";
+
+/// Argument of the `allow` and `deny` annotations.
+pub const LINT: &'static str = "lint";
+
+
+/// Annotation to declare the precedence level of an alternative, as
+/// in `#[precedence(level="2")]`. Lower levels bind more tightly.
+pub const PRECEDENCE: &'static str = "precedence";
+
+/// Argument of the `precedence` annotation.
+pub const LEVEL: &'static str = "level";
+
+/// Annotation to declare the associativity of an alternative, as in
+/// `#[assoc(side="left")]`.
+pub const ASSOC: &'static str = "assoc";
+
+/// Argument of the `assoc` annotation.
+pub const SIDE: &'static str = "side";
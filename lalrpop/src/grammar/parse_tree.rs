@@ -5,9 +5,11 @@ some pre-expansion and so forth before creating the proper AST.
 
 */
 
-use intern::{intern, InternedString};
+use collections::Map;
+use intern::{self, intern, InternedString};
 use lexer::dfa::DFA;
-use grammar::consts::{LALR, RECURSIVE_ASCENT, TABLE_DRIVEN, TEST_ALL};
+use grammar::consts::{ALLOW, DENY, LALR, LANE_TABLE, RECOVERY, RECOVERY_LIMIT, RECURSIVE_ASCENT,
+                      TABLE_DRIVEN, TEST_ALL};
 use grammar::repr::{self as r, NominalTypeRepr, TypeRepr};
 use grammar::pattern::Pattern;
 use message::Content;
@@ -45,11 +47,70 @@ impl Into<Box<Content>> for Span {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum GrammarItem {
     ExternToken(ExternToken),
+    Import(Import),
     InternToken(InternToken),
+    MatchToken(MatchToken),
     Nonterminal(NonterminalData),
+    Skip(Skip),
     Use(String),
 }
 
+/// An `import "common.lalrpop";` declaration. The path is relative
+/// to the file containing the declaration; the nonterminals and `use`
+/// items of the imported file are added to the grammar by
+/// `build::parse_grammar`, which then removes the `import` itself.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Import {
+    pub span: Span,
+    pub path: InternedString,
+}
+
+/// A `skip { r"\s+", r"//[^\n]*" }` declaration, listing the text
+/// that the internal tokenizer discards between tokens. Folded into
+/// the `InternToken` by the token check.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Skip {
+    pub span: Span,
+    pub literals: Vec<(Span, TerminalLiteral)>,
+}
+
+/// A `match { ... } else { ... }` declaration, which gives the
+/// literals of the internal tokenizer explicit priorities (earlier
+/// blocks win) and optionally renames them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchToken {
+    pub span: Span,
+    pub contents: Vec<MatchContents>,
+}
+
+/// One `{ ... }` block of a `match` declaration.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchContents {
+    pub items: Vec<MatchItem>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MatchItem {
+    // `_`: every literal of the grammar not listed elsewhere
+    CatchAll(Span),
+
+    // `r"[a-z]+"`: the literal, referred to by itself
+    Unmapped(Span, TerminalLiteral),
+
+    // `r"[a-z]+" => "ID"`: the literal, referred to as `"ID"`
+    Mapped(Span, TerminalLiteral, TerminalString),
+}
+
+impl MatchItem {
+    pub fn span(&self) -> Span {
+        match *self {
+            MatchItem::CatchAll(span) => span,
+            MatchItem::Unmapped(span, _) => span,
+            MatchItem::Mapped(span, _, _) => span,
+        }
+    }
+}
+
 /// Intern tokens are not typed by the user: they are synthesized in
 /// the absence of an "extern" declaration with information about the
 /// string literals etc that appear in the grammar.
@@ -58,9 +119,29 @@ pub struct InternToken {
     /// Set of `r"foo"` and `"foo"` literals extracted from the
     /// grammar. Sorted by order of increasing precedence.
     pub literals: Vec<TerminalLiteral>,
+
+    /// The terminal that the grammar uses for each literal renamed
+    /// by a `match` declaration.
+    pub renames: Map<TerminalLiteral, TerminalString>,
+
+    /// Literals to discard between tokens, from a `skip` declaration.
+    /// `None` means that no declaration was given, in which case
+    /// whitespace is skipped. In the DFA, these come after
+    /// `literals`.
+    pub skip: Option<Vec<TerminalLiteral>>,
+
     pub dfa: DFA
 }
 
+impl InternToken {
+    /// The terminal by which the grammar refers to `literal`.
+    pub fn terminal(&self, literal: TerminalLiteral) -> TerminalString {
+        self.renames.get(&literal)
+                    .cloned()
+                    .unwrap_or(TerminalString::Literal(literal))
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ExternToken {
     pub span: Span,
@@ -159,12 +240,25 @@ pub struct NonterminalData {
 pub struct Annotation {
     pub id_span: Span,
     pub id: InternedString,
+
+    // `#[id(name="value")]`; the span covers `name`
+    pub arg: Option<AnnotationArg>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AnnotationArg {
+    pub name_span: Span,
+    pub name: InternedString,
+    pub value: InternedString,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Alternative {
     pub span: Span,
 
+    // #[precedence(level="N")], #[assoc(side="left")]
+    pub annotations: Vec<Annotation>,
+
     pub expr: ExprSymbol,
 
     // if C, only legal in macros
@@ -343,6 +437,18 @@ impl Grammar {
                   .flat_map(|i| i.as_intern_token())
                   .next()
     }
+
+    pub fn match_token(&self) -> Option<&MatchToken> {
+        self.items.iter()
+                  .flat_map(|i| i.as_match_token())
+                  .next()
+    }
+
+    pub fn skip(&self) -> Option<&Skip> {
+        self.items.iter()
+                  .flat_map(|i| i.as_skip())
+                  .next()
+    }
 }
 
 impl GrammarItem {
@@ -357,8 +463,11 @@ impl GrammarItem {
         match *self {
             GrammarItem::Nonterminal(ref d) => Some(d),
             GrammarItem::Use(..) => None,
+            GrammarItem::Import(..) => None,
             GrammarItem::ExternToken(..) => None,
             GrammarItem::InternToken(..) => None,
+            GrammarItem::MatchToken(..) => None,
+            GrammarItem::Skip(..) => None,
         }
     }
 
@@ -366,8 +475,11 @@ impl GrammarItem {
         match *self {
             GrammarItem::Nonterminal(..) => None,
             GrammarItem::Use(..) => None,
+            GrammarItem::Import(..) => None,
             GrammarItem::ExternToken(ref d) => Some(d),
             GrammarItem::InternToken(..) => None,
+            GrammarItem::MatchToken(..) => None,
+            GrammarItem::Skip(..) => None,
         }
     }
 
@@ -375,8 +487,35 @@ impl GrammarItem {
         match *self {
             GrammarItem::Nonterminal(..) => None,
             GrammarItem::Use(..) => None,
+            GrammarItem::Import(..) => None,
             GrammarItem::ExternToken(..) => None,
             GrammarItem::InternToken(ref d) => Some(d),
+            GrammarItem::MatchToken(..) => None,
+            GrammarItem::Skip(..) => None,
+        }
+    }
+
+    pub fn as_skip(&self) -> Option<&Skip> {
+        match *self {
+            GrammarItem::Nonterminal(..) => None,
+            GrammarItem::Use(..) => None,
+            GrammarItem::Import(..) => None,
+            GrammarItem::ExternToken(..) => None,
+            GrammarItem::InternToken(..) => None,
+            GrammarItem::MatchToken(..) => None,
+            GrammarItem::Skip(ref d) => Some(d),
+        }
+    }
+
+    pub fn as_match_token(&self) -> Option<&MatchToken> {
+        match *self {
+            GrammarItem::Nonterminal(..) => None,
+            GrammarItem::Use(..) => None,
+            GrammarItem::Import(..) => None,
+            GrammarItem::ExternToken(..) => None,
+            GrammarItem::InternToken(..) => None,
+            GrammarItem::MatchToken(ref d) => Some(d),
+            GrammarItem::Skip(..) => None,
         }
     }
 }
@@ -657,15 +796,48 @@ pub fn read_algorithm(annotations: &[Annotation], algorithm: &mut r::Algorithm)
     for annotation in annotations {
         if annotation.id == intern(LALR) {
             algorithm.lalr = true;
+        } else if annotation.id == intern(LANE_TABLE) {
+            algorithm.lane_table = true;
         } else if annotation.id == intern(TABLE_DRIVEN) {
             algorithm.codegen = r::LrCodeGeneration::TableDriven;
         } else if annotation.id == intern(RECURSIVE_ASCENT) {
             algorithm.codegen = r::LrCodeGeneration::RecursiveAscent;
         } else if annotation.id == intern(TEST_ALL) {
             algorithm.codegen = r::LrCodeGeneration::TestAll;
+        } else if annotation.id == intern(RECOVERY) {
+            let arg = annotation.arg.as_ref().unwrap();
+            algorithm.recovery = intern::read(|interner| {
+                r::RecoveryStrategy::from_str(interner.data(arg.value)).unwrap()
+            });
+        } else if annotation.id == intern(RECOVERY_LIMIT) {
+            let arg = annotation.arg.as_ref().unwrap();
+            algorithm.recovery_limit = intern::read(|interner| {
+                Some(interner.data(arg.value).parse().unwrap())
+            });
+        } else if annotation.id == intern(ALLOW) || annotation.id == intern(DENY) {
+            // see `read_lint_levels`
         } else {
             panic!("validation permitted unknown annotation: {:?}",
                     annotation.id);
         }
     }
 }
+
+pub fn read_lint_levels(annotations: &[Annotation]) -> Map<r::Lint, r::LintLevel> {
+    annotations.iter()
+               .filter_map(|annotation| {
+                   let level = if annotation.id == intern(ALLOW) {
+                       r::LintLevel::Allow
+                   } else if annotation.id == intern(DENY) {
+                       r::LintLevel::Deny
+                   } else {
+                       return None;
+                   };
+                   let arg = annotation.arg.as_ref().unwrap();
+                   let lint = intern::read(|interner| {
+                       r::Lint::from_str(interner.data(arg.value)).unwrap()
+                   });
+                   Some((lint, level))
+               })
+               .collect()
+}
@@ -46,7 +46,57 @@ impl<T> Pattern<T> {
     }
 }
 
+impl<T> Pattern<T> {
+    /// Renders the pattern as an expression that constructs a value
+    /// it matches, using `choose_fn` for each `<T>`. Returns `None` if
+    /// the pattern contains `_` or `..`, or if `choose_fn` does.
+    pub fn to_expression(&self, choose_fn: &mut FnMut(&T) -> Option<String>) -> Option<String> {
+        self.kind.to_expression(choose_fn)
+    }
+}
+
 impl<T> PatternKind<T> {
+    pub fn to_expression(&self, choose_fn: &mut FnMut(&T) -> Option<String>) -> Option<String> {
+        match *self {
+            PatternKind::Path(ref path) =>
+                Some(format!("{}", path)),
+            PatternKind::Enum(ref path, ref pats) => {
+                let exprs: Option<Vec<_>> =
+                    pats.iter().map(|pat| pat.to_expression(choose_fn)).collect();
+                exprs.map(|exprs| format!("{}({})", path, Sep(", ", &exprs)))
+            }
+            PatternKind::Struct(ref path, ref fields, false) => {
+                let exprs: Option<Vec<_>> =
+                    fields.iter()
+                          .map(|field| {
+                              field.pattern
+                                   .to_expression(choose_fn)
+                                   .map(|expr| format!("{}: {}", field.field_name, expr))
+                          })
+                          .collect();
+                exprs.map(|exprs| format!("{} {{ {} }}", path, Sep(", ", &exprs)))
+            }
+            PatternKind::Struct(_, _, true) =>
+                None,
+            PatternKind::Tuple(ref pats) => {
+                let exprs: Option<Vec<_>> =
+                    pats.iter().map(|pat| pat.to_expression(choose_fn)).collect();
+                exprs.map(|exprs| if exprs.len() == 1 {
+                    format!("({},)", exprs[0])
+                } else {
+                    format!("({})", Sep(", ", &exprs))
+                })
+            }
+            PatternKind::Underscore |
+            PatternKind::DotDot =>
+                None,
+            PatternKind::Usize(n) =>
+                Some(format!("{}", n)),
+            PatternKind::Choose(ref ty) =>
+                choose_fn(ty),
+        }
+    }
+
     pub fn map<U>(&self, map_fn: &mut FnMut(&T) -> U) -> PatternKind<U> {
         match *self {
             PatternKind::Path(ref path) =>
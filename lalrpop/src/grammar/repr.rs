@@ -58,6 +58,9 @@ pub struct Grammar {
     pub token_span: Span,
     pub conversions: Map<TerminalString, Pattern<TypeRepr>>,
     pub types: Types,
+
+    // from `#[allow(..)]` and `#[deny(..)]`; other lints warn
+    pub lint_levels: Map<Lint, LintLevel>,
 }
 
 /// For each terminal, we map it to a small integer from 0 to N.
@@ -79,7 +82,12 @@ pub struct NonterminalData {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Algorithm {
     pub lalr: bool,
+    pub lane_table: bool,
     pub codegen: LrCodeGeneration,
+    pub recovery: RecoveryStrategy,
+
+    // from `#[recovery_limit(max="N")]`, if any
+    pub recovery_limit: Option<usize>,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -89,6 +97,40 @@ pub enum LrCodeGeneration {
     TestAll,
 }
 
+/// How a table-driven parser resumes after shifting `!`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RecoveryStrategy {
+    /// Drop tokens until one can follow the `!`.
+    Panic,
+
+    /// First try inserting, deleting or substituting a single token,
+    /// then fall back to `Panic`.
+    Repair,
+}
+
+/// The definitions that are valid, but probably mistakes, that
+/// LALRPOP warns about.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Lint {
+    /// Nonterminals that no public nonterminal refers to.
+    UnreachableNonterminals,
+
+    /// Nonterminals that no finite input can match.
+    UnproductiveNonterminals,
+
+    /// Terminals of an `extern` token declaration that the grammar
+    /// does not use.
+    UnusedTerminals,
+}
+
+/// How a lint is reported.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum LintLevel {
+    Allow,
+    Warn,
+    Deny,
+}
+
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Parameter {
     pub name: InternedString,
@@ -103,6 +145,24 @@ pub struct Production {
     pub symbols: Vec<Symbol>,
     pub action: ActionFn,
     pub span: Span,
+
+    // from `#[precedence]` and `#[assoc]` annotations, if any; used
+    // to resolve shift/reduce conflicts
+    pub precedence: Option<Precedence>,
+}
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Precedence {
+    // lower levels bind more tightly
+    pub level: u32,
+    pub assoc: Option<Assoc>,
+}
+
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Assoc {
+    Left,
+    Right,
+    None,
 }
 
 #[derive(Copy, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
@@ -513,6 +573,10 @@ impl Grammar {
         &self.conversions[&t]
     }
 
+    pub fn lint_level(&self, lint: Lint) -> LintLevel {
+        self.lint_levels.get(&lint).cloned().unwrap_or(LintLevel::Warn)
+    }
+
     pub fn productions_for(&self, nonterminal: NonterminalString) -> &[Production] {
         match self.nonterminals.get(&nonterminal) {
             Some(v) => &v.productions[..],
@@ -543,11 +607,54 @@ impl Grammar {
     }
 }
 
+impl Assoc {
+    pub fn from_str(s: &str) -> Option<Assoc> {
+        match s {
+            "left" => Some(Assoc::Left),
+            "right" => Some(Assoc::Right),
+            "none" => Some(Assoc::None),
+            _ => None,
+        }
+    }
+}
+
+impl RecoveryStrategy {
+    pub fn from_str(s: &str) -> Option<RecoveryStrategy> {
+        match s {
+            "panic" => Some(RecoveryStrategy::Panic),
+            "repair" => Some(RecoveryStrategy::Repair),
+            _ => None,
+        }
+    }
+}
+
+impl Lint {
+    pub fn all() -> &'static [Lint] {
+        &[Lint::UnreachableNonterminals, Lint::UnproductiveNonterminals, Lint::UnusedTerminals]
+    }
+
+    /// The name of the lint in `#[allow(lint="..")]`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnreachableNonterminals => "unreachable_nonterminals",
+            Lint::UnproductiveNonterminals => "unproductive_nonterminals",
+            Lint::UnusedTerminals => "unused_terminals",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Option<Lint> {
+        Lint::all().iter().cloned().find(|lint| lint.name() == s)
+    }
+}
+
 impl Default for Algorithm {
     fn default() -> Self {
         Algorithm {
             lalr: false,
+            lane_table: false,
             codegen: LrCodeGeneration::TableDriven,
+            recovery: RecoveryStrategy::Panic,
+            recovery_limit: None,
         }
     }
 }
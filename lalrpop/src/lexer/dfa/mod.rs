@@ -3,10 +3,14 @@
 
 use collections::Set;
 use kernel_set::{Kernel, KernelSet};
+use petgraph::Graph;
+use petgraph::dot::Dot;
+use petgraph::graph::NodeIndex;
 use std::fmt::{Debug, Display, Formatter, Error};
 use std::rc::Rc;
 use lexer::re;
 use lexer::nfa::{self, NFA, NFAConstructionError, NFAStateIndex, Test};
+use util::DotLabel;
 
 #[cfg(test)]
 mod test;
@@ -281,6 +285,34 @@ impl DFA {
     fn state(&self, index: DFAStateIndex) -> &State {
         &self.states[index.0]
     }
+
+    /// Renders the DFA in graphviz's `.dot` format. Accepting states
+    /// are labeled with what they accept, `names[i]` being the name
+    /// of the `i`th regular expression. Edges into the reject state
+    /// are left out.
+    pub fn to_dot(&self, names: &[String]) -> String {
+        let mut graph = Graph::new();
+        for (index, state) in self.states.iter().enumerate() {
+            let label = match state.kind {
+                Kind::Accepts(nfa) => format!("DFA{}\naccepts {}", index, names[nfa.0]),
+                Kind::Reject => format!("DFA{}\nreject", index),
+                Kind::Neither => format!("DFA{}", index),
+            };
+            graph.add_node(DotLabel(label));
+        }
+        for (index, state) in self.states.iter().enumerate() {
+            let edges = state.test_edges
+                             .iter()
+                             .map(|&(test, target)| (format!("{:?}", test), target))
+                             .chain(Some((format!("other"), state.other_edge)));
+            for (label, target) in edges {
+                if self.state(target).kind != Kind::Reject {
+                    graph.add_edge(NodeIndex::new(index), NodeIndex::new(target.0), DotLabel(label));
+                }
+            }
+        }
+        format!("{}", Dot::new(&graph))
+    }
 }
 
 impl Item {
@@ -4,18 +4,12 @@ Generates an iterator type `__Matcher` that looks roughly like
 
 ```ignore
 mod __intern_token {
-    extern crate regex as __regex;
-
     pub struct __Matcher<'input> {
         text: &'input str,
         consumed: usize,
-        regex_set: __regex::RegexSet,
-        regex_vec: Vec<__regex::Regex>,
     }
 
-    impl __Matcher<'input> {
-        fn __tokenize(&self, text: &str) -> Option<(usize, usize)> { ... }
-    }
+    fn __tokenize(text: &str) -> Option<(usize, usize)> { ... }
 
     impl<'input> Iterator for __Matcher<'input> {
         type Item = Result<(usize, (usize, &'input str), usize), ParseError>;
@@ -26,12 +20,19 @@ mod __intern_token {
 }
 ```
 
+The function `__tokenize` is a direct encoding of the DFA that
+`normalize::token_check` built for the literals: it walks the input
+one character at a time, remembering the longest match seen so far,
+and returns the index and length of that match. If the grammar has a
+`skip { ... }` declaration, the literals it lists are part of the same
+DFA, numbered after the grammar's own literals; `next` discards their
+matches rather than trimming whitespace.
+
  */
 
-use intern;
-use lexer::re;
+use lexer::dfa::{DFA, DFAStateIndex, Kind};
 use grammar::parse_tree::InternToken;
-use grammar::repr::{Grammar, TerminalLiteral};
+use grammar::repr::Grammar;
 use rust::RustWrite;
 use std::io::{self, Write};
 
@@ -45,58 +46,18 @@ pub fn compile<W: Write>(
 
     rust!(out, "mod {}intern_token {{", prefix);
     try!(out.write_standard_uses(prefix));
-    rust!(out, "extern crate regex as {}regex;", prefix);
     rust!(out, "pub struct {}Matcher<'input> {{", prefix);
     rust!(out, "text: &'input str,"); // remaining input
     rust!(out, "consumed: usize,"); // number of chars consumed thus far
-    rust!(out, "regex_set: {}regex::RegexSet,", prefix);
-    rust!(out, "regex_vec: Vec<{}regex::Regex>,", prefix);
     rust!(out, "}}");
     rust!(out, "");
-    rust!(out, "impl<'input> {}Matcher<'input> {{", prefix);
-    rust!(out, "pub fn new(s: &'input str) -> {}Matcher<'input> {{", prefix);
 
-    // create a vector of rust string literals with the text of each
-    // regular expression
-    let regex_strings: Vec<String> = intern::read(|interner| {
-        intern_token.literals
-                    .iter()
-                    .map(|&literal| match literal {
-                        TerminalLiteral::Quoted(s) => re::parse_literal(interner.data(s)),
-                        TerminalLiteral::Regex(s) => re::parse_regex(interner.data(s)).unwrap(),
-                    })
-                    .map(|regex| {
-                        // make sure all regex are anchored at the beginning of the input
-                        format!("^{}", regex)
-                    })
-                    .map(|regex_str| {
-                        // create a rust string with text of the regex; the Debug impl
-                        // will add quotes and escape
-                        format!("{:?}", regex_str)
-                    })
-                    .collect()
-    });
-
-    rust!(out, "let {}strs: &[&str] = &[", prefix);
-    for literal in &regex_strings {
-        rust!(out, "{},", literal);
-    }
-    rust!(out, "];");
-    rust!(out, "let {}regex_set = {}regex::RegexSet::new({}strs).unwrap();",
-          prefix, prefix, prefix);
+    try!(compile_dfa(prefix, &intern_token.dfa, out));
+    rust!(out, "");
 
-    rust!(out, "let {}regex_vec = vec![", prefix);
-    for literal in &regex_strings {
-        rust!(out, "{}regex::Regex::new({}).unwrap(),", prefix, literal);
-    }
-    rust!(out, "];");
-
-    rust!(out, "{}Matcher {{", prefix);
-    rust!(out, "text: s,");
-    rust!(out, "consumed: 0,");
-    rust!(out, "regex_set: {}regex_set,", prefix);
-    rust!(out, "regex_vec: {}regex_vec,", prefix);
-    rust!(out, "}}"); // struct literal
+    rust!(out, "impl<'input> {}Matcher<'input> {{", prefix);
+    rust!(out, "pub fn new(s: &'input str) -> {}Matcher<'input> {{", prefix);
+    rust!(out, "{}Matcher {{ text: s, consumed: 0 }}", prefix);
     rust!(out, "}}"); // fn new()
     rust!(out, "}}"); // impl Matcher<'input>
     rust!(out, "");
@@ -107,67 +68,127 @@ pub fn compile<W: Write>(
     rust!(out, "");
     rust!(out, "fn next(&mut self) -> Option<Self::Item> {{");
 
-    // start by trimming whitespace from left
-    rust!(out, "let {}text = self.text.trim_left();", prefix);
-    rust!(out, "let {}whitespace = self.text.len() - {}text.len();", prefix, prefix);
-    rust!(out, "let {}start_offset = self.consumed + {}whitespace;", prefix, prefix);
+    rust!(out, "loop {{");
+
+    // start by trimming whitespace from left, unless the grammar
+    // declared its own `skip` literals, which the DFA matches instead
+    if intern_token.skip.is_none() {
+        rust!(out, "let {}text = self.text.trim_left();", prefix);
+        rust!(out, "let {}whitespace = self.text.len() - {}text.len();", prefix, prefix);
+        rust!(out, "let {}start_offset = self.consumed + {}whitespace;", prefix, prefix);
+    } else {
+        rust!(out, "let {}text = self.text;", prefix);
+        rust!(out, "let {}start_offset = self.consumed;", prefix);
+    }
 
     // if nothing left, return None
     rust!(out, "if {}text.is_empty() {{", prefix);
     rust!(out, "self.text = {}text;", prefix);
     rust!(out, "self.consumed = {}start_offset;", prefix);
-    rust!(out, "None");
-    rust!(out, "}} else {{");
-
-    // otherwise, use regex-set to find list of matching tokens
-    rust!(out, "let {}matches = self.regex_set.matches({}text);", prefix, prefix);
+    rust!(out, "return None;");
+    rust!(out, "}}");
 
-    // if nothing matched, return an error
-    rust!(out, "if !{}matches.matched_any() {{", prefix);
-    rust!(out, "Some(Err({}lalrpop_util::ParseError::InvalidToken {{", prefix);
-    rust!(out, "location: {}start_offset,", prefix);
-    rust!(out, "}}))");
-    rust!(out, "}} else {{");
-
-    // otherwise, have to find longest, highest-priority match. We have the literals
-    // sorted in order of increasing precedence, so we'll iterate over them one by one,
-    // checking if each one matches, and remembering the longest one.
-    rust!(out, "let mut {}longest_match = 0;", prefix); // length of longest match
-    rust!(out, "let mut {}index = 0;", prefix); // index of longest match
-    rust!(out, "for {}i in 0 .. {} {{", prefix, intern_token.literals.len());
-    rust!(out, "if {}matches.matched({}i) {{", prefix, prefix);
-
-    // re-run the regex to find out how long this particular match
-    // was, then compare that against the longest-match so far. Note
-    // that the order of the tuple is carefully constructed to ensure
-    // that (a) we get the longest-match but (b) if two matches are
-    // equal, we get the largest index. This is because the indices
-    // are sorted in order of increasing priority, and because we know
-    // that indices of equal priority cannot both match (because of
-    // the DFA check).
-    rust!(out, "let {}match = self.regex_vec[{}i].find({}text).unwrap();", prefix, prefix, prefix);
-    rust!(out, "let {}len = {}match.end();", prefix, prefix);
-    rust!(out, "if {}len >= {}longest_match {{", prefix, prefix);
-    rust!(out, "{}longest_match = {}len;", prefix, prefix);
-    rust!(out, "{}index = {}i;", prefix, prefix);
-    rust!(out, "}}"); // if is longest match
-    rust!(out, "}}"); // if matches.matched(i)
-    rust!(out, "}}"); // for loop
-
-    // transform the result into the expected return value
-    rust!(out, "let {}result = &{}text[..{}longest_match];", prefix, prefix, prefix);
-    rust!(out, "let {}remaining = &{}text[{}longest_match..];", prefix, prefix, prefix);
-    rust!(out, "let {}end_offset = {}start_offset + {}longest_match;", prefix, prefix, prefix);
+    // otherwise, run the DFA to find the longest, highest-priority match
+    rust!(out, "match {}tokenize({}text) {{", prefix, prefix);
+    rust!(out, "Some(({}index, {}length)) => {{", prefix, prefix);
+    rust!(out, "let {}result = &{}text[..{}length];", prefix, prefix, prefix);
+    rust!(out, "let {}remaining = &{}text[{}length..];", prefix, prefix, prefix);
+    rust!(out, "let {}end_offset = {}start_offset + {}length;", prefix, prefix, prefix);
     rust!(out, "self.text = {}remaining;", prefix);
     rust!(out, "self.consumed = {}end_offset;", prefix);
-    rust!(out, "Some(Ok(({}start_offset, ({}index, {}result), {}end_offset)))",
+
+    // skipped literals are numbered after those of the grammar
+    if intern_token.skip.as_ref().map_or(false, |skip| !skip.is_empty()) {
+        rust!(out, "if {}index >= {} {{", prefix, intern_token.literals.len());
+        rust!(out, "continue;");
+        rust!(out, "}}");
+    }
+
+    rust!(out, "return Some(Ok(({}start_offset, ({}index, {}result), {}end_offset)));",
           prefix, prefix, prefix, prefix);
+    rust!(out, "}}"); // Some
 
-    rust!(out, "}}"); // else
-    rust!(out, "}}"); // else
+    // if nothing matched, return an error
+    rust!(out, "None => {{");
+    rust!(out, "return Some(Err({}lalrpop_util::ParseError::InvalidToken {{ \
+                location: {}start_offset }}));",
+          prefix, prefix);
+    rust!(out, "}}"); // None
+    rust!(out, "}}"); // match
+
+    rust!(out, "}}"); // loop
     rust!(out, "}}"); // fn
     rust!(out, "}}"); // impl
     rust!(out, "}}"); // mod
     Ok(())
 }
 
+/// Emits `fn __tokenize(text: &str) -> Option<(usize, usize)>`, which
+/// returns the index of the literal matched at the start of `text`
+/// along with the length of the match. Each DFA state becomes one arm
+/// of a `match` on the current state, which in turn matches on the
+/// next character; there is no arm for the reject state, since
+/// entering it just means we return the longest match found so far.
+fn compile_dfa<W: Write>(prefix: &str,
+                         dfa: &DFA,
+                         out: &mut RustWrite<W>)
+                         -> io::Result<()> {
+    rust!(out, "fn {}tokenize(text: &str) -> Option<(usize, usize)> {{", prefix);
+    rust!(out, "let mut {}chars = text.char_indices();", prefix);
+    rust!(out, "let mut {}current_match: Option<(usize, usize)> = None;", prefix);
+    rust!(out, "let mut {}current_state: usize = 0;", prefix);
+    rust!(out, "loop {{");
+    rust!(out, "match {}current_state {{", prefix);
+    for (index, state) in dfa.states.iter().enumerate() {
+        if state.kind == Kind::Reject {
+            continue;
+        }
+
+        rust!(out, "{} => {{", index);
+        rust!(out, "let ({}index, {}ch) = match {}chars.next() {{ \
+                    Some(p) => p, None => return {}current_match }};",
+              prefix, prefix, prefix, prefix);
+        rust!(out, "match {}ch as u32 {{", prefix);
+        for &(test, target) in &state.test_edges {
+            if test.is_char() {
+                rust!(out, "{} => {{", test.start);
+            } else {
+                rust!(out, "{} ... {} => {{", test.start, test.end - 1);
+            }
+            try!(goto(prefix, dfa, target, out));
+            rust!(out, "}}");
+        }
+        rust!(out, "_ => {{");
+        try!(goto(prefix, dfa, state.other_edge, out));
+        rust!(out, "}}");
+        rust!(out, "}}"); // match ch
+        rust!(out, "}}"); // state arm
+    }
+    rust!(out, "_ => {{ panic!(\"invalid state {{}}\", {}current_state); }}", prefix);
+    rust!(out, "}}"); // match state
+    rust!(out, "}}"); // loop
+    rust!(out, "}}"); // fn
+    Ok(())
+}
+
+/// Emits the code to move to `target` after consuming `__ch`.
+fn goto<W: Write>(prefix: &str,
+                  dfa: &DFA,
+                  target: DFAStateIndex,
+                  out: &mut RustWrite<W>)
+                  -> io::Result<()> {
+    match dfa.states[target.index()].kind {
+        Kind::Accepts(nfa) => {
+            rust!(out, "{}current_match = Some(({}, {}index + {}ch.len_utf8()));",
+                  prefix, nfa.index(), prefix, prefix);
+        }
+        Kind::Neither => { }
+        Kind::Reject => {
+            rust!(out, "return {}current_match;", prefix);
+            return Ok(());
+        }
+    }
+    rust!(out, "{}current_state = {};", prefix, target.index());
+    rust!(out, "continue;");
+    Ok(())
+}
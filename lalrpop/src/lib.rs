@@ -53,4 +53,6 @@ mod util;
 pub use api::Configuration;
 pub use api::process_root;
 pub use api::process_root_unconditionally;
+pub use build::{Diagnostic, Error, InterpretError, ParseTree};
+pub use grammar::parse_tree::Span;
 pub use ascii_canvas::style;
@@ -1,3 +1,6 @@
+use std::cell::RefCell;
+use std::mem;
+
 #[derive(Clone)]
 pub struct Log {
     level: Level,
@@ -31,9 +34,56 @@ impl Log {
         where M: FnOnce() -> String
     {
         if self.level >= level {
-            println!("{}", message());
+            let message = message();
+            if !write_captured(format!("{}\n", message).as_bytes()) {
+                println!("{}", message);
+            }
+        }
+    }
+}
+
+thread_local! {
+    static CAPTURED: RefCell<Option<Vec<u8>>> = RefCell::new(None)
+}
+
+/// Runs `op`, collecting everything printed on this thread in the
+/// meantime (log messages and error reports alike) rather than
+/// printing it. This keeps the output of files processed in parallel
+/// from being interleaved.
+pub fn capture<OP, RET>(op: OP) -> (RET, Vec<u8>)
+    where OP: FnOnce() -> RET
+{
+    struct Restore(Option<Vec<u8>>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let old_value = self.0.take();
+            CAPTURED.with(|c| *c.borrow_mut() = old_value);
         }
     }
+
+    let _restore = Restore(CAPTURED.with(|c| mem::replace(&mut *c.borrow_mut(), Some(vec![]))));
+    let result = op();
+    let output = CAPTURED.with(|c| c.borrow_mut().take().unwrap());
+    (result, output)
+}
+
+/// Whether the output of this thread is being captured.
+pub fn is_capturing() -> bool {
+    CAPTURED.with(|c| c.borrow().is_some())
+}
+
+/// Appends `bytes` to the output being captured on this thread, if
+/// any. Returns false if nothing is being captured, in which case the
+/// caller should print `bytes` itself.
+pub fn write_captured(bytes: &[u8]) -> bool {
+    CAPTURED.with(|c| match *c.borrow_mut() {
+        Some(ref mut output) => {
+            output.extend_from_slice(bytes);
+            true
+        }
+        None => false,
+    })
 }
 
 macro_rules! log {
@@ -5,8 +5,8 @@ use kernel_set;
 use grammar::repr::*;
 use lr1::core::*;
 use lr1::first;
-use lr1::lane_table::*;
 use lr1::lookahead::*;
+use lr1::precedence;
 use std::rc::Rc;
 use tls::Tls;
 
@@ -17,12 +17,6 @@ pub fn build_lr1_states<'grammar>(grammar: &'grammar Grammar,
                                   start: NonterminalString)
                                   -> LR1Result<'grammar>
 {
-    // this is just here to squash dead code warnings while work on
-    // lane-table proceeds
-    if false {
-        let _ = build_lane_table_states(grammar, start);
-    }
-
     profile! {
         &Tls::session(),
         "LR(1) state construction",
@@ -142,7 +136,9 @@ impl<'grammar, L: LookaheadBuild> LR<'grammar, L> {
                 this_state.reductions.push((item.lookahead.clone(), item.production));
             }
 
-            // check for shift-reduce conflicts (reduce-reduce detected above)
+            // settle whatever conflicts the user's precedence
+            // annotations cover, then check for the rest
+            L::resolve_conflicts(&mut this_state);
             conflicts.extend(L::conflicts(&this_state));
 
             // extract a new state
@@ -306,6 +302,11 @@ pub trait LookaheadBuild: Lookahead {
                                remainder: &[Symbol],
                                lookahead: &Self)
                                -> Vec<Item<'grammar, Self>>;
+
+    // Removes the actions that lose out to others according to the
+    // `#[precedence]` and `#[assoc]` annotations of the productions
+    // involved. This requires lookahead, so it is a no-op for LR(0).
+    fn resolve_conflicts<'grammar>(this_state: &mut State<'grammar, Self>);
 }
 
 impl LookaheadBuild for Nil {
@@ -317,6 +318,9 @@ impl LookaheadBuild for Nil {
     {
         lr.items(nt, 0, &lookahead)
     }
+
+    fn resolve_conflicts<'grammar>(_this_state: &mut LR0State<'grammar>) {
+    }
 }
 
 impl LookaheadBuild for TokenSet {
@@ -329,4 +333,8 @@ impl LookaheadBuild for TokenSet {
         let first_set = lr.first_sets.first1(remainder, lookahead);
         lr.items(nt, 0, &first_set)
     }
+
+    fn resolve_conflicts<'grammar>(this_state: &mut LR1State<'grammar>) {
+        precedence::resolve_conflicts(this_state)
+    }
 }
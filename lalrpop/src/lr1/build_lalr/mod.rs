@@ -5,6 +5,7 @@ use itertools::Itertools;
 use lr1::build;
 use lr1::core::*;
 use lr1::lookahead::*;
+use lr1::precedence;
 use grammar::repr::*;
 use std::rc::Rc;
 use std::mem;
@@ -118,8 +119,10 @@ pub fn collapse_to_lalr_states<'grammar>(lr_states: &[LR1State<'grammar>])
         }
     }
 
-    // Finally, create the new states and detect conflicts
-    let lr1_states: Vec<_> = lalr1_states.into_iter()
+    // Finally, create the new states and detect conflicts. Merging
+    // lookaheads can reintroduce shift/reduce conflicts that were
+    // resolved in the LR(1) states, so resolve them again.
+    let mut lr1_states: Vec<_> = lalr1_states.into_iter()
                                          .map(|lr| {
                                              State {
                                                  index: lr.index,
@@ -134,6 +137,10 @@ pub fn collapse_to_lalr_states<'grammar>(lr_states: &[LR1State<'grammar>])
                                          })
                                          .collect();
 
+    for state in &mut lr1_states {
+        precedence::resolve_conflicts(state);
+    }
+
     let conflicts: Vec<_> = lr1_states.iter()
                                       .flat_map(|s| TokenSet::conflicts(s))
                                       .collect();
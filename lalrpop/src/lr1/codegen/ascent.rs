@@ -6,9 +6,11 @@ use collections::{Multimap, Set};
 use grammar::repr::{Grammar, NonterminalString, Production, Symbol, TerminalString, TypeParameter,
                     TypeRepr};
 use lr1::core::*;
+use lr1::expected_terminals;
 use lr1::lookahead::Token;
 use lr1::state_graph::StateGraph;
 use rust::RustWrite;
+use std::cmp;
 use std::io::{self, Write};
 use tls::Tls;
 use util::{Escape, Sep};
@@ -42,6 +44,11 @@ struct RecursiveAscent<'ascent, 'grammar> {
 
     /// type parameters for the `Nonterminal` type
     nonterminal_type_params: Vec<TypeParameter>,
+
+    /// true if the grammar uses `!`, in which case the state
+    /// functions thread the pending error along and may return early
+    /// (without consuming their inputs) to pop states during recovery
+    error_recovery: bool,
 }
 
 /// Tracks the suffix of the stack (that is, top-most elements) that any
@@ -134,31 +141,47 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
                                                      .cloned()
                                                      .collect();
 
-        let state_inputs = states.iter()
-                                 .map(|state| Self::state_input_for(state))
-                                 .collect();
-
-        CodeGenerator::new(grammar,
-                           user_start_symbol,
-                           start_symbol,
-                           states,
-                           out,
-                           false,
-                           action_module,
-                           RecursiveAscent {
-                               graph: graph,
-                               state_inputs: state_inputs,
-                               nonterminal_type_params: nonterminal_type_params,
-                           })
+        let mut ascent = CodeGenerator::new(grammar,
+                                            user_start_symbol,
+                                            start_symbol,
+                                            states,
+                                            out,
+                                            false,
+                                            action_module,
+                                            RecursiveAscent {
+                                                graph: graph,
+                                                state_inputs: vec![],
+                                                nonterminal_type_params: nonterminal_type_params,
+                                                error_recovery: false,
+                                            });
+
+        let error_recovery = ascent.uses_error_recovery();
+        ascent.custom.error_recovery = error_recovery;
+        ascent.custom.state_inputs = states.iter()
+                                           .map(|state| Self::state_input_for(state, error_recovery))
+                                           .collect();
+        ascent
     }
 
     /// Compute the stack suffix that the state expects on entry.
-    fn state_input_for(state: &'ascent LR1State<'grammar>) -> StackSuffix<'grammar> {
+    ///
+    /// When the grammar uses error recovery, a state may be popped
+    /// before it has consumed its inputs, so only the most recently
+    /// pushed symbol (which is dropped along with the state) is
+    /// taken by value; the rest stay with the states that pushed them.
+    fn state_input_for(state: &'ascent LR1State<'grammar>,
+                       error_recovery: bool)
+                       -> StackSuffix<'grammar> {
         let max_prefix = state.max_prefix();
         let will_pop = state.will_pop();
+        let len_fixed = if error_recovery {
+            cmp::min(will_pop.len(), 1)
+        } else {
+            will_pop.len()
+        };
         StackSuffix {
             all: max_prefix,
-            len_optional: max_prefix.len() - will_pop.len(),
+            len_optional: max_prefix.len() - len_fixed,
         }
     }
 
@@ -192,6 +215,12 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
             rust!(self.out, "{}({}),", Escape(nt), ty);
         }
 
+        // returned by states that are popped while recovering from
+        // an error, until we reach one that can shift `!`
+        if self.custom.error_recovery {
+            rust!(self.out, "{}error_recovery,", self.prefix);
+        }
+
         rust!(self.out, "}}");
         Ok(())
     }
@@ -204,15 +233,41 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
         try!(self.start_parser_fn());
         try!(self.define_tokens());
 
+        let recovery_args = if self.custom.error_recovery {
+            rust!(self.out,
+                  "let {}error: &mut Option<{}> = &mut None;",
+                  self.prefix,
+                  self.types.parse_error_type());
+            rust!(self.out,
+                  "let {}last_location: &mut {} = &mut Default::default();",
+                  self.prefix,
+                  self.types.terminal_loc_type());
+            format!("{}error, {}last_location, ", self.prefix, self.prefix)
+        } else {
+            String::new()
+        };
+
         try!(self.next_token("lookahead", "tokens"));
         rust!(self.out,
-              "match try!({}state0({}&mut {}tokens, {}lookahead, {})) {{",
+              "match try!({}state0({}&mut {}tokens, {}{}lookahead, {})) {{",
               self.prefix,
               self.grammar.user_parameter_refs(),
               self.prefix,
+              recovery_args,
               self.prefix,
               phantom_data);
 
+        // every state was popped without finding one that could
+        // recover from the error
+        if self.custom.error_recovery {
+            rust!(self.out,
+                  "(_, {}Nonterminal::{}error_recovery) => {{",
+                  self.prefix,
+                  self.prefix);
+            rust!(self.out, "Err({}error.take().unwrap())", self.prefix);
+            rust!(self.out, "}}");
+        }
+
         // extra tokens?
         rust!(self.out, "(Some({}lookahead), _) => {{", self.prefix);
         rust!(self.out, "Err({}lalrpop_util::ParseError::ExtraToken {{ token: {}lookahead }})",
@@ -288,12 +343,25 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
         // set to true if goto actions are worth generating
         let mut fallthrough = false;
 
+        // while recovering from an error, the lookahead that caused
+        // it must not be shifted or reduced until it is accepted by
+        // the state that shifted `!`
+        let guard = if self.custom.error_recovery {
+            format!(" if {}error.is_none()", self.prefix)
+        } else {
+            String::new()
+        };
+
         rust!(self.out, "match {}lookahead {{", self.prefix);
 
         // first emit shifts:
         for (&terminal, &next_index) in &this_state.shifts {
+            if terminal == TerminalString::Error {
+                continue;
+            }
+
             let sym_name = format!("{}sym{}", self.prefix, inputs.len());
-            try!(self.consume_terminal(terminal, sym_name));
+            try!(self.consume_terminal(terminal, sym_name, &guard));
 
             // transition to the new state
             if try!(self.transition("result", stack_suffix, next_index, &["tokens"])) {
@@ -314,16 +382,26 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
                                                         })
                                                         .collect();
         for (production, tokens) in reductions {
-            for (index, &token) in tokens.iter().enumerate() {
-                let pattern = match token {
-                    Token::Terminal(s) => format!("Some({})", self.match_terminal_pattern(s)),
-                    Token::Error => panic!("Error recovery is not implemented for recursive ascent parsers"),
-                    Token::EOF => format!("None"),
-                };
-                if index < tokens.len() - 1 {
+            // reductions on `!` are performed by the error arm below
+            let patterns: Vec<_> = tokens.iter()
+                                         .filter_map(|&token| match token {
+                                             Token::Terminal(TerminalString::Error) |
+                                             Token::Error => None,
+                                             Token::Terminal(s) => {
+                                                 Some(format!("Some({})",
+                                                              self.match_terminal_pattern(s)))
+                                             }
+                                             Token::EOF => Some(format!("None")),
+                                         })
+                                         .collect();
+            if patterns.is_empty() {
+                continue;
+            }
+            for (index, pattern) in patterns.iter().enumerate() {
+                if index < patterns.len() - 1 {
                     rust!(self.out, "{} |", pattern);
                 } else {
-                    rust!(self.out, "{} => {{", pattern);
+                    rust!(self.out, "{}{} => {{", pattern, guard);
                 }
             }
 
@@ -342,26 +420,38 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
         // if we hit this, the next token is not recognized, so generate an error
         rust!(self.out, "_ => {{");
         // The terminals which would have resulted in a successful parse in this state
-        let successful_terminals = self.grammar.terminals.all.iter().filter(|&terminal| {
-                this_state.shifts.contains_key(terminal) ||
-                    this_state.reductions
-                        .iter()
-                        .any(|&(ref t, _)| t.contains(Token::Terminal(*terminal)))
-            });
-        rust!(self.out, "return Err({}lalrpop_util::ParseError::UnrecognizedToken {{", self.prefix);
-        rust!(self.out, "token: {}lookahead,", self.prefix);
+        let successful_terminals = expected_terminals(self.grammar, this_state);
+        if self.custom.error_recovery {
+            rust!(self.out, "if {}error.is_none() {{", self.prefix);
+            rust!(self.out,
+                  "*{}error = Some({}lalrpop_util::ParseError::UnrecognizedToken {{",
+                  self.prefix,
+                  self.prefix);
+            rust!(self.out, "token: {}lookahead.clone(),", self.prefix);
+        } else {
+            rust!(self.out, "return Err({}lalrpop_util::ParseError::UnrecognizedToken {{", self.prefix);
+            rust!(self.out, "token: {}lookahead,", self.prefix);
+        }
         rust!(self.out, "expected: vec![");
         for terminal in successful_terminals {
             rust!(self.out, "r###\"{}\"###.to_string(),", terminal);
         }
         rust!(self.out, "]");
         rust!(self.out, "}});");
+        if self.custom.error_recovery {
+            rust!(self.out, "}}");
+            if try!(self.emit_error_action(this_index, stack_suffix)) {
+                fallthrough = true;
+            }
+        }
         rust!(self.out, "}}");
 
         rust!(self.out, "}}"); // match
 
+        let error_index = this_state.shifts.get(&TerminalString::Error).cloned();
+
         // finally, emit gotos (if relevant)
-        if fallthrough && !this_state.gotos.is_empty() {
+        if fallthrough && (!this_state.gotos.is_empty() || error_index.is_some()) {
             rust!(self.out, "loop {{");
 
             // In most states, we know precisely when the top stack
@@ -423,9 +513,28 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
                 rust!(self.out, "}}");
             }
 
+            // A state popped during error recovery; if we can shift
+            // `!`, recovery stops here.
+            if let Some(error_index) = error_index {
+                let guard = if self.accepts_eof(error_index) {
+                    String::new()
+                } else {
+                    format!(" if {}lookahead.is_some()", self.prefix)
+                };
+                rust!(self.out,
+                      "{}Nonterminal::{}error_recovery{} => {{",
+                      self.prefix,
+                      self.prefix,
+                      guard);
+                try!(self.emit_error_recovery(stack_suffix, error_index));
+                rust!(self.out, "}}");
+            }
+
             // Errors are not possible in the goto phase; a missing entry
-            // indicates parse successfully completed, so just bail out.
-            if this_state.gotos.len() != self.grammar.nonterminals.keys().len() {
+            // indicates parse successfully completed (or that this
+            // state is being popped by error recovery), so just bail out.
+            if self.custom.error_recovery ||
+               this_state.gotos.len() != self.grammar.nonterminals.keys().len() {
                 rust!(self.out, "_ => {{");
                 rust!(self.out,
                       "return Ok(({}lookahead, {}nt));",
@@ -446,6 +555,136 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
         Ok(())
     }
 
+    /// Emits the action taken once an error has been recorded in
+    /// `this_index`: reduce on `!` if we can, otherwise start
+    /// recovering if we can shift `!`, otherwise pop this state by
+    /// returning. Returns `true` if the goto loop must run afterwards.
+    fn emit_error_action(&mut self,
+                         this_index: StateIndex,
+                         stack_suffix: StackSuffix<'grammar>)
+                         -> io::Result<bool> {
+        let this_state = &self.states[this_index.0];
+
+        let error_token = Token::Terminal(TerminalString::Error);
+        let reduction = this_state.reductions
+                                  .iter()
+                                  .find(|&&(ref tokens, _)| tokens.contains(error_token))
+                                  .map(|&(_, production)| production);
+        if let Some(production) = reduction {
+            try!(self.emit_reduce_action("result", stack_suffix, production));
+            if production.symbols.len() > 0 {
+                rust!(self.out, "return Ok({}result);", self.prefix);
+                return Ok(false);
+            }
+            return Ok(true);
+        }
+
+        if let Some(&error_index) = this_state.shifts.get(&TerminalString::Error) {
+            // at EOF, we can only recover if EOF can follow the `!`
+            if !self.accepts_eof(error_index) {
+                rust!(self.out, "if {}lookahead.is_none() {{", self.prefix);
+                try!(self.emit_pop_state());
+                rust!(self.out, "}}");
+            }
+            try!(self.emit_error_recovery(stack_suffix, error_index));
+            return Ok(true);
+        }
+
+        try!(self.emit_pop_state());
+        Ok(false)
+    }
+
+    fn emit_pop_state(&mut self) -> io::Result<()> {
+        rust!(self.out,
+              "return Ok(({}lookahead, {}Nonterminal::{}error_recovery));",
+              self.prefix,
+              self.prefix,
+              self.prefix);
+        Ok(())
+    }
+
+    /// Emits code that drops tokens until one that `error_index` (the
+    /// state reached by shifting `!`) accepts, then shifts the `!`
+    /// symbol, carrying the pending error and the dropped tokens.
+    fn emit_error_recovery(&mut self,
+                           stack_suffix: StackSuffix<'grammar>,
+                           error_index: StateIndex)
+                           -> io::Result<()> {
+        let error_state = &self.states[error_index.0];
+
+        rust!(self.out, "let mut {}lookahead = {}lookahead;", self.prefix, self.prefix);
+        rust!(self.out, "let mut {}dropped_tokens = vec![];", self.prefix);
+        rust!(self.out, "loop {{");
+        rust!(self.out, "match {}lookahead {{", self.prefix);
+        let patterns: Vec<_> = expected_terminals(self.grammar, error_state)
+                                   .into_iter()
+                                   .map(|terminal| {
+                                       format!("Some({})", self.match_terminal_pattern(terminal))
+                                   })
+                                   .collect();
+        for (index, pattern) in patterns.iter().enumerate() {
+            if index < patterns.len() - 1 {
+                rust!(self.out, "{} |", pattern);
+            } else {
+                rust!(self.out, "{} => break,", pattern);
+            }
+        }
+        if self.accepts_eof(error_index) {
+            rust!(self.out, "None => break,");
+        } else {
+            rust!(self.out, "None => return Err({}error.take().unwrap()),", self.prefix);
+        }
+        rust!(self.out, "_ => {{ }}");
+        rust!(self.out, "}}"); // match
+        rust!(self.out,
+              "{}dropped_tokens.push({}lookahead.unwrap());",
+              self.prefix,
+              self.prefix);
+        try!(self.emit_next_token(&format!("{}lookahead", self.prefix), "tokens"));
+        rust!(self.out, "}}"); // loop
+
+        // the `!` spans the first token dropped, or else the token
+        // that we resume with, or else (at EOF) the end of the input
+        rust!(self.out,
+              "let ({}start, {}end) = match ({}dropped_tokens.first(), {}lookahead.as_ref()) {{",
+              self.prefix,
+              self.prefix,
+              self.prefix,
+              self.prefix);
+        rust!(self.out,
+              "(Some({}tok), _) | (None, Some({}tok)) => ({}tok.0.clone(), {}tok.2.clone()),",
+              self.prefix,
+              self.prefix,
+              self.prefix,
+              self.prefix);
+        rust!(self.out,
+              "(None, None) => ({}last_location.clone(), {}last_location.clone()),",
+              self.prefix,
+              self.prefix);
+        rust!(self.out, "}};");
+        rust!(self.out,
+              "let {}sym{} = ({}start, {}lalrpop_util::ErrorRecovery {{",
+              self.prefix,
+              stack_suffix.len(),
+              self.prefix,
+              self.prefix);
+        rust!(self.out, "error: {}error.take().unwrap(),", self.prefix);
+        rust!(self.out, "dropped_tokens: {}dropped_tokens,", self.prefix);
+        rust!(self.out, "repair: None,");
+        rust!(self.out, "}}, {}end);", self.prefix);
+
+        try!(self.transition("result", stack_suffix, error_index, &["tokens", "lookahead"]));
+        Ok(())
+    }
+
+    /// True if `state` takes some action when the input is exhausted.
+    fn accepts_eof(&self, state: StateIndex) -> bool {
+        self.states[state.0]
+            .reductions
+            .iter()
+            .any(|&(ref tokens, _)| tokens.contains(Token::EOF))
+    }
+
     fn emit_state_fn_header(&mut self,
                             fn_kind: &str, // e.g. "state", "custom"
                             fn_index: usize, // state index, custom kind index, etc
@@ -523,12 +762,25 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
         // lookahead in as an argument, but rather we load it as the
         // first thing in this function; this saves some space because
         // there are more edges than there are states in the graph.
+        // (The `!` symbol is shifted without consuming the lookahead,
+        // so states reached that way are given it as well.)
         let starts_with_terminal = fixed_prefix.last()
-                                               .map(|l| l.is_terminal())
+                                               .map(|l| {
+                                                   l.is_terminal() &&
+                                                   *l != Symbol::Terminal(TerminalString::Error)
+                                               })
                                                .unwrap_or(false);
 
 
         let mut base_args = vec![format!("{}tokens: &mut {}TOKENS", self.prefix, self.prefix)];
+        if self.custom.error_recovery {
+            base_args.push(format!("{}error: &mut Option<{}>",
+                                   self.prefix,
+                                   self.types.parse_error_type()));
+            base_args.push(format!("{}last_location: &mut {}",
+                                   self.prefix,
+                                   self.types.terminal_loc_type()));
+        }
         if !starts_with_terminal {
             base_args.push(format!("{}lookahead: Option<{}>", self.prefix, triple_type));
         }
@@ -683,9 +935,14 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
 
         let transfer_syms = try!(self.pop_syms(optional, fixed, next_inputs));
 
-        let other_args = other_args.iter()
-                                   .map(|s| format!("{}{}", self.prefix, s))
-                                   .collect();
+        let mut other_args: Vec<_> = other_args.iter()
+                                               .map(|s| format!("{}{}", self.prefix, s))
+                                               .collect();
+        if self.custom.error_recovery {
+            // these follow `tokens`; see `fn_args`
+            other_args.insert(1, format!("{}error", self.prefix));
+            other_args.insert(2, format!("{}last_location", self.prefix));
+        }
 
         let fn_name = format!("{}state{}", self.prefix, next_index.0);
 
@@ -844,7 +1101,11 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
 
     /// Emit a pattern that matches `id` and extracts its value, storing
     /// that value as `let_name`.
-    fn consume_terminal(&mut self, id: TerminalString, let_name: String) -> io::Result<()> {
+    fn consume_terminal(&mut self,
+                        id: TerminalString,
+                        let_name: String,
+                        guard: &str)
+                        -> io::Result<()> {
         let mut pattern_names = vec![];
         let pattern = self.grammar.pattern(id).map(&mut |_| {
             let index = pattern_names.len();
@@ -860,7 +1121,7 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
 
         pattern = format!("({}loc1, {}, {}loc2)", self.prefix, pattern, self.prefix);
 
-        rust!(self.out, "Some({}) => {{", pattern);
+        rust!(self.out, "Some({}){} => {{", pattern, guard);
 
         rust!(self.out,
               "let {} = ({}loc1, ({}), {}loc2);",
@@ -877,13 +1138,26 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent,
     }
 
     fn next_token(&mut self, lookahead: &str, tokens: &str) -> io::Result<()> {
+        let binding = format!("let {}{}", self.prefix, lookahead);
+        self.emit_next_token(&binding, tokens)
+    }
+
+    /// Fetches the next token into `place` (e.g. `let __lookahead`).
+    fn emit_next_token(&mut self, place: &str, tokens: &str) -> io::Result<()> {
         rust!(self.out,
-              "let {}{} = match {}{}.next() {{",
-              self.prefix,
-              lookahead,
+              "{} = match {}{}.next() {{",
+              place,
               self.prefix,
               tokens);
-        rust!(self.out, "Some(Ok(v)) => Some(v),");
+        if self.custom.error_recovery {
+            // remember where the input ends, for errors found at EOF
+            rust!(self.out, "Some(Ok(v)) => {{");
+            rust!(self.out, "*{}last_location = v.2.clone();", self.prefix);
+            rust!(self.out, "Some(v)");
+            rust!(self.out, "}}");
+        } else {
+            rust!(self.out, "Some(Ok(v)) => Some(v),");
+        }
         rust!(self.out, "None => None,");
         if self.grammar.intern_token.is_some() {
             // when we generate the tokenizer, the generated errors are `ParseError` values
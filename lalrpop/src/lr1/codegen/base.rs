@@ -2,6 +2,7 @@
 
 use grammar::repr::*;
 use lr1::core::*;
+use lr1::lookahead::Token;
 use rust::RustWrite;
 use std::io::{self, Write};
 use util::Sep;
@@ -65,6 +66,40 @@ impl<'codegen, 'grammar, W: Write, C> CodeGenerator<'codegen, 'grammar, W, C> {
         }
     }
 
+    /// True if any state shifts or reduces on the `!` (error)
+    /// terminal, in which case the generated parser must perform
+    /// error recovery.
+    pub fn uses_error_recovery(&self) -> bool {
+        self.states.iter().any(|state| {
+            state.shifts.contains_key(&TerminalString::Error) ||
+                state.reductions
+                    .iter()
+                    .any(|&(ref t, _)| t.contains(Token::Terminal(TerminalString::Error)))
+        })
+    }
+
+    /// The limit from `#[recovery_limit(max="N")]`, if any and if
+    /// the grammar uses error recovery at all. Parsers with a limit
+    /// return the number of recoveries alongside their result.
+    pub fn recovery_limit(&self) -> Option<usize> {
+        if self.uses_error_recovery() {
+            self.grammar.algorithm.recovery_limit
+        } else {
+            None
+        }
+    }
+
+    /// The type of a successful parse: the value of the start symbol,
+    /// paired with the number of recoveries if there is a limit.
+    pub fn result_type(&self) -> String {
+        let nonterminal_type = self.types.nonterminal_type(self.start_symbol);
+        if self.recovery_limit().is_some() {
+            format!("(usize, {})", nonterminal_type)
+        } else {
+            format!("{}", nonterminal_type)
+        }
+    }
+
     pub fn write_parse_mod<F>(&mut self, body: F) -> io::Result<()>
         where F: FnOnce(&mut Self) -> io::Result<()>
     {
@@ -136,7 +171,7 @@ impl<'codegen, 'grammar, W: Write, C> CodeGenerator<'codegen, 'grammar, W, C> {
                                           type_parameters,
                                           parameters,
                                           format!("Result<{}, {}>",
-                                                  self.types.nonterminal_type(self.start_symbol),
+                                                  self.result_type(),
                                                   parse_error_type),
                                           where_clauses));
         rust!(self.out, "{{");
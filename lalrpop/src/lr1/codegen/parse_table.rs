@@ -1,7 +1,9 @@
 //! A compiler from an LR(1) table to a traditional table driven parser.
 
 use collections::{Map, Set};
+use grammar::pattern::Pattern;
 use grammar::repr::*;
+use intern;
 use lr1::core::*;
 use lr1::lookahead::Token;
 use rust::RustWrite;
@@ -67,60 +69,81 @@ pub fn compile<'grammar, W: Write>(grammar: &'grammar Grammar,
 // }
 // ```
 //
-// The action parser function looks like this (pseudo-code):
+// The state of the parse lives in a struct, so that tokens can be
+// pushed into it one at a time (pseudo-code):
+//
+// ```
+// pub struct ExprParser<...> {
+//     // user parameters, like `scale: i32`
+//     states: Vec<i32>,                        // initial state is zero
+//     symbols: Vec<(Location, Symbol, Location)>,
+//     last_location: Location,
+//     recovery: Option<...>,                   // only with error recovery
+// }
+//
+// fn push<TOKEN: ToTriple>(&mut self, token: TOKEN) -> Result<(), Error> {
+//     // converts the token, then calls `consume()`
+// }
+//
+// fn consume(&mut self, lookahead: (Location, Token, Location)) -> Result<(), Error> {
+//     // Code to determine which terminal the lookahead is; emitted
+//     // by `token_to_integer()`.
+//     let integer = match lookahead {
+//         (_, PatternForTerminal0(...), _) => 0,
+//         ...
+//     };
+//
+//     // If we are dropping tokens to recover from an error, see
+//     // whether we can resume with this one (see below).
+//
+//     loop {
+//         let state = *states.last().unwrap() as usize;
+//         let action = ACTION[state * NUM_STATES + integer];
+//         if action > 0 { // shift
+//             let symbol = match lookahead {
+//                (l, PatternForTerminal0(...), r) => {
+//                    (l, Value::VariantForTerminal0(...), r),
+//                }
+//                ...
+//             };
+//             states.push(action - 1);
+//             symbols.push(symbol);
+//             return Ok(());
+//         } else if action < 0 { // reduce
+//             try!(reduce(action, Some(&lookahead.0), &mut states, &mut symbols));
+//         } else {
+//             try_error_recovery(...)?;
+//         }
+//     }
+// }
+//
+// fn finish(self) -> Result<T, Error> {
+//     // Process EOF
+//     while let Some(state) = self.states.pop() {
+//         let action = EOF_ACTION[state * NUM_STATES];
+//         if action < 0 { // reduce
+//             try!(reduce(action, None, &mut states, &mut symbols));
+//         } else {
+//             // we actually attempt a variant on `error_recovery()` here first
+//             return Err(...);
+//         }
+//     }
+// }
+// ```
+//
+// The parser function just drives the struct:
 //
 // ```
 // fn parse_fn<TOKENS>(tokens: TOKENS) -> Result<T, Error>
 //    where TOKENS: Iterator<Item=Result<(Location, Token, Location), Error>>
 // {
-//    let mut states = vec![0]; // initial state is zero
-//    let mut symbols = vec![];
-//    'shift: loop {
-//        // Code to shift the next symbol and determine which terminal
-//        // it is; emitted by `shift_symbol()`.
-//        let lookahead = match tokens.next() {
-//            Some(Ok(l)) => l,
-//            None => break 'shift,
-//            Some(Err(e)) => return Err(e),
-//        };
-//        let integer = match lookahead {
-//            (_, PatternForTerminal0(...), _) => 0,
-//            ...
-//        };
-//
-//        // Code to process next symbol.
-//        'inner: loop {
-//            let symbol = match lookahead {
-//               (l, PatternForTerminal0(...), r) => {
-//                   (l, Value::VariantForTerminal0(...), r),
-//               }
-//               ...
-//            };
-//            let state = *states.last().unwrap() as usize;
-//            let action = ACTION[state * NUM_STATES + integer];
-//            if action > 0 { // shift
-//                states.push(action - 1);
-//                symbols.push(symbol);
-//                continue 'shift;
-//            } else if action < 0 { // reduce
-//                try!(reduce(action, Some(&lookahead.0), &mut states, &mut symbols));
-//            } else {
-//                try_error_recovery(...)?;
-//            }
-//        }
-//    }
-//
-//    // Process EOF
-//    while let Some(state) = self.states.pop() {
-//        let action = EOF_ACTION[state * NUM_STATES];
-//        if action < 0 { // reduce
-//            try!(reduce(action, None, &mut states, &mut symbols));
-//        } else {
-//            // we actually attempt a variant on `error_recovery()` here first
-//            return Err(...);
-//        }
-//    }
+//     let mut parser = ExprParser::new();
+//     for token in tokens {
+//         try!(parser.consume(try!(token)));
+//     }
+//     parser.finish()
 // }
+// ```
 //
 // // generated by `emit_reduce_actions()`
 // fn reduce(action: i32, lookahead_start: Option<&L>,
@@ -177,35 +200,19 @@ pub fn compile<'grammar, W: Write>(grammar: &'grammar Grammar,
 //         }
 //     }
 //
-//     // skip input until we find something that can follow `error` in this state
-//     let start = lookahead.0.clone();
-//     let end = lookahead.2.clone();
-//     loop {
-//         if ACTION[(error_state as usize - 1) * NUM_STATES + integer] != 0 {
-//             let new_len = symbols.len() - (original_state_len - states.len());
-//             symbols.truncate(new_len);
-//             states.push(error_state - 1);
-//             symbols.push((start, Symbol::Termerror(error), end));
-//             continue 'inner;
-//         }
-//         lookahead = match tokens.next() {
-//             Some(Ok(v)) => v,
-//             None => break 'shift,
-//             Some(Err(e)) => return Err(lalrpop_util::ParseError::User { error: e }),
-//         };
-//
-//         // get next token:
-//         last_location = lookahead.2.clone();
-//         integer = match lookahead.1 {
-//             ... => ...,
-//             _ => {
-//                 return Err(lalrpop_util::ParseError::UnrecognizedToken {
-//                     token: Some(lookahead),
-//                     expected: vec![],
-//                 });
-//             }
-//         };
+//     // if this token can follow `error` in this state, shift the
+//     // `error` and carry on; otherwise, drop it and remember where we
+//     // are, so that `consume()` can check the next token the same way
+//     if ACTION[(error_state as usize - 1) * NUM_STATES + integer] != 0 {
+//         let new_len = symbols.len() - (original_state_len - states.len());
+//         symbols.truncate(new_len);
+//         states.push(error_state - 1);
+//         symbols.push((start, Symbol::Termerror(error), end));
+//         continue; // with the same lookahead
 //     }
+//     dropped_tokens.push(lookahead);
+//     recovery = Some((error, error_state, original_state_len, dropped_tokens));
+//     return Ok(());
 // }
 // ```
 
@@ -297,6 +304,7 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         self.write_parse_mod(|this| {
             try!(this.write_value_type_defn());
             try!(this.write_parse_table());
+            try!(this.write_parser_struct());
             try!(this.write_parser_fn());
             try!(this.emit_reduce_actions());
             try!(this.emit_downcast_fns());
@@ -385,6 +393,98 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
 
         try!(self.emit_expected_tokens_fn());
 
+        if self.uses_error_repair() {
+            try!(self.emit_simulate_fn());
+        }
+
+        Ok(())
+    }
+
+    /// Emits `simulate()`, which checks whether the parser could
+    /// shift `error_state` and then the given terminals (`None` being
+    /// EOF) without an error, without executing any actions. This is
+    /// how the repair strategy evaluates candidate repairs.
+    fn emit_simulate_fn(&mut self) -> io::Result<()> {
+        // For each reduce action, the number of states it pops and the
+        // column of its nonterminal in the GOTO table.
+        rust!(self.out, "const {}REDUCE: &'static [(usize, usize)] = &[", self.prefix);
+        for production in self.grammar.nonterminals.values().flat_map(|nt| &nt.productions) {
+            let index = self.custom
+                            .all_nonterminals
+                            .iter()
+                            .position(|&x| x == production.nonterminal)
+                            .unwrap();
+            rust!(self.out, "({}, {}),", production.symbols.len(), index);
+        }
+        rust!(self.out, "];");
+
+        let start_index = self.custom
+                              .all_nonterminals
+                              .iter()
+                              .position(|&x| x == self.start_symbol)
+                              .unwrap();
+        rust!(self.out,
+              "fn {}simulate({}states: &[i32], {}error_state: i32, {}integers: &[Option<usize>]) \
+               -> bool {{",
+              self.prefix,
+              self.prefix,
+              self.prefix,
+              self.prefix);
+        rust!(self.out, "let mut {}states = {}states.to_vec();", self.prefix, self.prefix);
+        rust!(self.out, "{}states.push({}error_state);", self.prefix, self.prefix);
+        rust!(self.out, "for {}integer in {}integers {{", self.prefix, self.prefix);
+        rust!(self.out, "loop {{");
+        rust!(self.out,
+              "let {}state = *{}states.last().unwrap() as usize;",
+              self.prefix,
+              self.prefix);
+        rust!(self.out, "let {}action = match *{}integer {{", self.prefix, self.prefix);
+        rust!(self.out,
+              "Some({}integer) => {}ACTION[{}state * {} + {}integer],",
+              self.prefix,
+              self.prefix,
+              self.prefix,
+              self.grammar.terminals.all.len(),
+              self.prefix);
+        rust!(self.out, "None => {}EOF_ACTION[{}state],", self.prefix, self.prefix);
+        rust!(self.out, "}};");
+        rust!(self.out, "if {}action > 0 {{", self.prefix);
+        rust!(self.out, "{}states.push({}action - 1);", self.prefix, self.prefix);
+        rust!(self.out, "break;");
+        rust!(self.out, "}} else if {}action < 0 {{", self.prefix);
+        rust!(self.out,
+              "let ({}len, {}nonterminal) = {}REDUCE[(-{}action - 1) as usize];",
+              self.prefix,
+              self.prefix,
+              self.prefix,
+              self.prefix);
+        rust!(self.out, "if {}nonterminal == {} {{", self.prefix, start_index);
+        rust!(self.out, "return true;");
+        rust!(self.out, "}}");
+        rust!(self.out, "let {}states_len = {}states.len();", self.prefix, self.prefix);
+        rust!(self.out,
+              "{}states.truncate({}states_len - {}len);",
+              self.prefix,
+              self.prefix,
+              self.prefix);
+        rust!(self.out,
+              "let {}state = *{}states.last().unwrap() as usize;",
+              self.prefix,
+              self.prefix);
+        rust!(self.out,
+              "{}states.push({}GOTO[{}state * {} + {}nonterminal] - 1);",
+              self.prefix,
+              self.prefix,
+              self.prefix,
+              self.grammar.nonterminals.len(),
+              self.prefix);
+        rust!(self.out, "}} else {{");
+        rust!(self.out, "return false;");
+        rust!(self.out, "}}");
+        rust!(self.out, "}}"); // loop
+        rust!(self.out, "}}"); // for
+        rust!(self.out, "true");
+        rust!(self.out, "}}");
         Ok(())
     }
 
@@ -403,41 +503,220 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         }
     }
 
-    fn write_parser_fn(&mut self) -> io::Result<()> {
-        let phantom_data_expr = self.phantom_data_expr();
+    fn write_parser_struct(&mut self) -> io::Result<()> {
+        let parser_type = self.parser_type();
+        let type_parameters = format!("<{}>", Sep(", ", &self.grammar.type_parameters));
+        let where_clauses = if self.grammar.where_clauses.is_empty() {
+            String::new()
+        } else {
+            format!(" where {}", Sep(", ", &self.grammar.where_clauses))
+        };
 
-        try!(self.start_parser_fn());
+        // The parser state that survives between tokens: the
+        // user's parameters, the two stacks, and (if the grammar
+        // uses error recovery) the tokens dropped so far while
+        // recovering.
+        rust!(self.out,
+              "pub struct {}{}{} {{",
+              parser_type,
+              type_parameters,
+              where_clauses);
+        for parameter in &self.grammar.parameters {
+            rust!(self.out, "{}: {},", parameter.name, parameter.ty);
+        }
+        rust!(self.out,
+              "{}states: ::std::vec::Vec<i32>,",
+              self.prefix);
+        rust!(self.out,
+              "{}symbols: ::std::vec::Vec<{}>,",
+              self.prefix,
+              self.spanned_symbol_type());
+        rust!(self.out,
+              "{}last_location: {},",
+              self.prefix,
+              self.types.terminal_loc_type());
+        if self.uses_error_recovery() {
+            // (error, error state, original number of states, dropped tokens)
+            rust!(self.out,
+                  "{}recovery: Option<({}, i32, usize, ::std::vec::Vec<{}>)>,",
+                  self.prefix,
+                  self.types.parse_error_type(),
+                  self.types.triple_type());
+        }
+        if self.recovery_limit().is_some() {
+            rust!(self.out, "{}recoveries: usize,", self.prefix);
+        }
+        rust!(self.out, "{}phantom: {},", self.prefix, self.parser_phantom_data_type());
+        rust!(self.out, "}}");
 
-        try!(self.define_tokens());
+        rust!(self.out,
+              "impl{} {}{}{} {{",
+              type_parameters,
+              parser_type,
+              type_parameters,
+              where_clauses);
+
+        try!(self.write_parser_new_fn());
+        if self.grammar.intern_token.is_none() {
+            try!(self.write_parser_push_fn());
+        }
+        try!(self.write_parser_consume_fn());
+        try!(self.write_parser_finish_fn());
+        if self.uses_error_repair() {
+            try!(self.write_parser_find_repair_fn());
+        }
 
-        // State and data stack.
-        rust!(self.out, "let mut {}states = vec![0_i32];", self.prefix);
-        rust!(self.out, "let mut {}symbols = vec![];", self.prefix);
+        rust!(self.out, "}}");
+        Ok(())
+    }
 
-        rust!(self.out, "let mut {}integer;", self.prefix);
-        rust!(self.out, "let mut {}lookahead;", self.prefix);
-        // The location of the last token is necessary for for error recovery at EOF (or they would not have
-        // a location)
-        rust!(self.out, "let mut {}last_location = Default::default();", self.prefix);
-
-        // Outer loop: each time we continue around this loop, we
-        // shift a new token from the input. We break from the loop
-        // when the end of the input is reached (we return early if an
-        // error occurs).
-        rust!(self.out, "'{}shift: loop {{", self.prefix);
-        if DEBUG_PRINT {
-            rust!(self.out, "println!(\"outer loop\");");
+    fn write_parser_new_fn(&mut self) -> io::Result<()> {
+        let parameters: Vec<_> = self.grammar
+                                     .parameters
+                                     .iter()
+                                     .map(|parameter| format!("{}: {}", parameter.name, parameter.ty))
+                                     .collect();
+        rust!(self.out, "pub fn new({}) -> Self {{", Sep(", ", &parameters));
+        rust!(self.out, "{} {{", self.parser_type());
+        for parameter in &self.grammar.parameters {
+            rust!(self.out, "{}: {},", parameter.name, parameter.name);
         }
+        rust!(self.out, "{}states: vec![0_i32],", self.prefix);
+        rust!(self.out, "{}symbols: vec![],", self.prefix);
+        // The location of the last token is necessary for for error
+        // recovery at EOF (or they would not have a location)
+        rust!(self.out, "{}last_location: Default::default(),", self.prefix);
+        if self.uses_error_recovery() {
+            rust!(self.out, "{}recovery: None,", self.prefix);
+        }
+        if self.recovery_limit().is_some() {
+            rust!(self.out, "{}recoveries: 0,", self.prefix);
+        }
+        rust!(self.out, "{}phantom: ::std::marker::PhantomData,", self.prefix);
+        rust!(self.out, "}}");
+        rust!(self.out, "}}");
+        Ok(())
+    }
 
-        // Read next token from input; defines `integer` and `symbol`.
-        try!(self.next_token());
+    fn write_parser_push_fn(&mut self) -> io::Result<()> {
+        let mut user_type_parameters = String::new();
+        for type_parameter in &self.grammar.type_parameters {
+            user_type_parameters.push_str(&format!("{}, ", type_parameter));
+        }
+
+        // `parse_Foo` feeds tokens to `consume` directly, so this may
+        // well go unused
+        rust!(self.out, "#[allow(dead_code)]");
+        rust!(self.out,
+              "pub fn push<{}TOKEN: {}ToTriple<{}Error={}>>(&mut self, {}token: {}TOKEN) \
+               -> Result<(), {}> {{",
+              self.prefix,
+              self.prefix,
+              user_type_parameters,
+              self.types.error_type(),
+              self.prefix,
+              self.prefix,
+              self.types.parse_error_type());
+        rust!(self.out,
+              "match {}ToTriple::to_triple({}token) {{",
+              self.prefix,
+              self.prefix);
+        rust!(self.out, "Ok(v) => self.{}consume(v),", self.prefix);
+        rust!(self.out,
+              "Err(e) => Err({}lalrpop_util::ParseError::User {{ error: e }}),",
+              self.prefix);
+        rust!(self.out, "}}");
+        rust!(self.out, "}}");
+        Ok(())
+    }
+
+    fn write_parser_consume_fn(&mut self) -> io::Result<()> {
+        rust!(self.out,
+              "fn {}consume(&mut self, mut {}lookahead: {}) -> Result<(), {}> {{",
+              self.prefix,
+              self.prefix,
+              self.types.triple_type(),
+              self.types.parse_error_type());
+        rust!(self.out,
+              "self.{}last_location = {}lookahead.2.clone();",
+              self.prefix,
+              self.prefix);
+        try!(self.borrow_stacks());
+        rust!(self.out, "let mut {}integer;", self.prefix);
         try!(self.token_to_integer());
 
-        // Loop.
-        rust!(self.out, "'{}inner: loop {{", self.prefix);
-        if DEBUG_PRINT {
-            rust!(self.out, "println!(\"inner loop\");");
+        // With the repair strategy, a token inserted in front of the
+        // lookahead (or substituted for a dropped one) is processed
+        // first; the lookahead waits here until it has been shifted.
+        if self.uses_error_repair() {
+            rust!(self.out,
+                  "let mut {}pending: Option<({}, usize)> = None;",
+                  self.prefix,
+                  self.types.triple_type());
         }
+
+        // If we are in the midst of error recovery, either this token
+        // can follow the `error` and we resume parsing, or we drop it
+        // too.
+        if self.uses_error_recovery() {
+            rust!(self.out,
+                  "if let Some(({}error, {}error_state, {}original_state_len, mut {}dropped_tokens)) \
+                   = self.{}recovery.take() {{",
+                  self.prefix,
+                  self.prefix,
+                  self.prefix,
+                  self.prefix,
+                  self.prefix);
+            let no_repair = if self.uses_error_repair() {
+                try!(self.delete_or_substitute(&format!("Some({}integer)", self.prefix)));
+                rust!(self.out,
+                      "if let Some(({}integer1, {}replacement)) = {}substitution {{",
+                      self.prefix,
+                      self.prefix,
+                      self.prefix);
+                rust!(self.out,
+                      "{}pending = Some(({}lookahead, {}integer));",
+                      self.prefix,
+                      self.prefix,
+                      self.prefix);
+                rust!(self.out, "{}lookahead = {}replacement;", self.prefix, self.prefix);
+                rust!(self.out, "{}integer = {}integer1;", self.prefix, self.prefix);
+                rust!(self.out, "}}");
+                format!("{}repair.is_none() && ", self.prefix)
+            } else {
+                String::new()
+            };
+            rust!(self.out,
+                  "if {}{}ACTION[({}error_state as usize - 1) * {} + {}integer] == 0 {{",
+                  no_repair,
+                  self.prefix,
+                  self.prefix,
+                  self.grammar.terminals.all.len(),
+                  self.prefix);
+            if DEBUG_PRINT {
+                rust!(self.out, "println!(\"Skipping token: {{}}\", {}integer);", self.prefix);
+            }
+            rust!(self.out, "{}dropped_tokens.push({}lookahead);", self.prefix, self.prefix);
+            rust!(self.out,
+                  "self.{}recovery = Some(({}error, {}error_state, {}original_state_len, \
+                   {}dropped_tokens));",
+                  self.prefix,
+                  self.prefix,
+                  self.prefix,
+                  self.prefix,
+                  self.prefix);
+            rust!(self.out, "return Ok(());");
+            rust!(self.out, "}}");
+            let start = format!("{}dropped_tokens[0].0.clone()", self.prefix);
+            let end = format!("{}dropped_tokens[0].2.clone()", self.prefix);
+            let dropped_tokens = format!("{}dropped_tokens", self.prefix);
+            let repair = self.repair_variable();
+            try!(self.push_error_symbol(&start, &end, &dropped_tokens, &repair));
+            rust!(self.out, "}}");
+        }
+
+        // Loop, reducing until we can shift the lookahead.
+        rust!(self.out, "loop {{");
         rust!(self.out,
               "let {}state = *{}states.last().unwrap() as usize;",
               self.prefix,
@@ -478,25 +757,26 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
               self.prefix,
               self.prefix,
               self.prefix);
-        rust!(self.out, "continue '{}shift;", self.prefix);
+        if self.uses_error_repair() {
+            rust!(self.out,
+                  "if let Some(({}next, {}next_integer)) = {}pending.take() {{",
+                  self.prefix,
+                  self.prefix,
+                  self.prefix);
+            rust!(self.out, "{}lookahead = {}next;", self.prefix, self.prefix);
+            rust!(self.out, "{}integer = {}next_integer;", self.prefix, self.prefix);
+            rust!(self.out, "continue;");
+            rust!(self.out, "}}");
+        }
+        rust!(self.out, "return Ok(());");
 
         // Reduce.
         rust!(self.out, "}} else if {}action < 0 {{", self.prefix);
         if DEBUG_PRINT {
             rust!(self.out, "println!(\"--> reduce\");");
         }
-        rust!(self.out,
-              "if let Some(r) = {}reduce({}{}action, Some(&{}lookahead.0), &mut {}states, &mut \
-               {}symbols, {}) {{",
-              self.prefix,
-              self.grammar.user_parameter_refs(),
-              self.prefix,
-              self.prefix,
-              self.prefix,
-              self.prefix,
-              phantom_data_expr);
-        rust!(self.out, "return r;");
-        rust!(self.out, "}}");
+        let lookahead_start = format!("Some(&{}lookahead.0)", self.prefix);
+        try!(self.reduce(&lookahead_start, false));
 
         // Error.
         rust!(self.out, "}} else {{");
@@ -505,10 +785,7 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
             let prefix = self.prefix;
             try!(self.unrecognized_token_error(&format!("Some({}lookahead.clone())", prefix)));
             rust!(self.out, "let mut {}dropped_tokens = Vec::new();", self.prefix);
-            let lookahead_start = format!("Some(&{}lookahead.0)", self.prefix);
-            try!(self.error_recovery(&lookahead_start, ""));
-            rust!(self.out, "let {}start = {}lookahead.0.clone();", self.prefix, self.prefix);
-            rust!(self.out, "let {}end = {}lookahead.2.clone();", self.prefix, self.prefix);
+            try!(self.error_recovery(&lookahead_start, "", false));
 
             if DEBUG_PRINT {
                 rust!(self.out, "println!(\"Attempting to recover on state: {{}}, error_state: {{}}, symbols: {{}}\", {}state, {}error_state - 1, {}symbols.len());",
@@ -517,37 +794,17 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
                     self.prefix);
             }
 
-            // Loop which drops tokens until parsing can resume again
-            rust!(self.out, "loop {{");
+            // Either resume parsing with this token right away, or
+            // start dropping tokens until parsing can resume again
             rust!(self.out, "if {}ACTION[({}error_state as usize - 1) * {} + {}integer] != 0 {{",
                 self.prefix,
                 self.prefix,
                 self.grammar.terminals.all.len(),
                 self.prefix);
-            rust!(self.out, "let {}new_len = {}symbols.len() - ({}original_state_len - {}states.len());",
-                self.prefix,
-                self.prefix,
-                self.prefix,
-                self.prefix);
-            rust!(self.out, "{}symbols.truncate({}new_len);",
-                self.prefix,
-                self.prefix);
-            rust!(self.out, "{}states.push({}error_state - 1);",
-                self.prefix,
-                self.prefix);
-            rust!(self.out, "let {}recovery = {}lalrpop_util::ErrorRecovery {{",
-                self.prefix,
-                self.prefix);
-            rust!(self.out, "error: {}error,", self.prefix);
-            rust!(self.out, "dropped_tokens: {}dropped_tokens,", self.prefix);
-            rust!(self.out, "}};");
-            rust!(self.out,
-                "{}symbols.push(({}start, {}Symbol::Termerror({}recovery), {}end));",
-                self.prefix,
-                self.prefix,
-                self.prefix,
-                self.prefix,
-                self.prefix);
+            let start = format!("{}lookahead.0.clone()", self.prefix);
+            let end = format!("{}lookahead.2.clone()", self.prefix);
+            let dropped_tokens = format!("{}dropped_tokens", self.prefix);
+            try!(self.push_error_symbol(&start, &end, &dropped_tokens, "None"));
 
             if DEBUG_PRINT {
                 rust!(self.out, "println!(\"Recovering on state: {{}}, lookahead: {{}}, symbols: {{}}\", {}error_state - 1, {}integer, {}symbols.len());",
@@ -556,21 +813,57 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
                     self.prefix);
             }
 
-            rust!(self.out, "continue '{}inner;", self.prefix);
-            rust!(self.out, "}}");// if ACTION
+            rust!(self.out, "continue;");
+            rust!(self.out, "}}"); // if ACTION
+
+            // Or, with the repair strategy, insert a token that lets
+            // us resume with this one
+            if self.uses_error_repair() {
+                rust!(self.out,
+                      "if let Some(({}integer1, {}inserted)) = \
+                       Self::{}find_repair(&{}states[..], {}error_state - 1, Some({}integer)) {{",
+                      self.prefix,
+                      self.prefix,
+                      self.prefix,
+                      self.prefix,
+                      self.prefix,
+                      self.prefix);
+                rust!(self.out,
+                      "let {}inserted = ({}lookahead.0.clone(), {}inserted, {}lookahead.0.clone());",
+                      self.prefix,
+                      self.prefix,
+                      self.prefix,
+                      self.prefix);
+                let repair = format!("Some({}lalrpop_util::Repair::Insert({}inserted.clone()))",
+                                     self.prefix,
+                                     self.prefix);
+                try!(self.push_error_symbol(&start, &end, &dropped_tokens, &repair));
+                rust!(self.out,
+                      "{}pending = Some(({}lookahead, {}integer));",
+                      self.prefix,
+                      self.prefix,
+                      self.prefix);
+                rust!(self.out, "{}lookahead = {}inserted;", self.prefix, self.prefix);
+                rust!(self.out, "{}integer = {}integer1;", self.prefix, self.prefix);
+                rust!(self.out, "continue;");
+                rust!(self.out, "}}");
+            }
 
+            if DEBUG_PRINT {
+                rust!(self.out, "println!(\"Skipping token: {{}}\", {}integer);", self.prefix);
+            }
             rust!(self.out, "{}dropped_tokens.push({}lookahead);",
                 self.prefix,
                 self.prefix);
-            try!(self.next_token());
-            try!(self.token_to_integer());
-
-            if DEBUG_PRINT {
-                rust!(self.out, "println!(\"Skipping token: {{}}\", {}integer);",
-                    self.prefix);
-            }
-
-            rust!(self.out, "}}"); // loop
+            rust!(self.out,
+                  "self.{}recovery = Some(({}error, {}error_state, {}original_state_len, \
+                   {}dropped_tokens));",
+                  self.prefix,
+                  self.prefix,
+                  self.prefix,
+                  self.prefix,
+                  self.prefix);
+            rust!(self.out, "return Ok(());");
         } else {
             let prefix = self.prefix;
             try!(self.unrecognized_token_error(&format!("Some({}lookahead)", prefix)));
@@ -581,10 +874,79 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
 
         rust!(self.out, "}}"); // reduce loop
 
-        rust!(self.out, "}}"); // shift loop
+        rust!(self.out, "}}"); // fn
+        Ok(())
+    }
+
+    fn write_parser_finish_fn(&mut self) -> io::Result<()> {
+        rust!(self.out,
+              "pub fn finish(mut self) -> Result<{}, {}> {{",
+              self.result_type(),
+              self.types.parse_error_type());
+
+        // A repair may have to push one more token before the EOF,
+        // which needs `self`; so with the repair strategy the stacks
+        // are only borrowed for as long as they are needed.
+        let repair = self.uses_error_repair();
+        if repair {
+            rust!(self.out, "let mut {}pending = None;", self.prefix);
+            rust!(self.out, "{{");
+        }
+        try!(self.borrow_stacks());
+
+        // If the input ended while we were dropping tokens, we can
+        // only resume if EOF can follow the `error`.
+        if self.uses_error_recovery() {
+            rust!(self.out,
+                  "if let Some(({}error, {}error_state, {}original_state_len, {}dropped_tokens)) \
+                   = self.{}recovery.take() {{",
+                  self.prefix,
+                  self.prefix,
+                  self.prefix,
+                  self.prefix,
+                  self.prefix);
+            let no_repair = if repair {
+                try!(self.delete_or_substitute("None"));
+                rust!(self.out,
+                      "{}pending = {}substitution.map(|(_, {}replacement)| {}replacement);",
+                      self.prefix,
+                      self.prefix,
+                      self.prefix,
+                      self.prefix);
+                format!("{}repair.is_none() && ", self.prefix)
+            } else {
+                String::new()
+            };
+            rust!(self.out,
+                  "if {}{}EOF_ACTION[{}error_state as usize - 1] == 0 {{",
+                  no_repair,
+                  self.prefix,
+                  self.prefix);
+            rust!(self.out, "return Err({}error);", self.prefix);
+            rust!(self.out, "}}");
+            let start = format!("{}dropped_tokens[0].0.clone()", self.prefix);
+            let end = format!("{}dropped_tokens[0].2.clone()", self.prefix);
+            let dropped_tokens = format!("{}dropped_tokens", self.prefix);
+            let repair = self.repair_variable();
+            try!(self.push_error_symbol(&start, &end, &dropped_tokens, &repair));
+            rust!(self.out, "}}");
+        }
+
+        if repair {
+            rust!(self.out, "}}");
+            rust!(self.out,
+                  "if let Some({}replacement) = {}pending {{",
+                  self.prefix,
+                  self.prefix);
+            rust!(self.out, "try!(self.{}consume({}replacement));", self.prefix, self.prefix);
+            rust!(self.out, "}}");
+        }
 
         // EOF loop
         rust!(self.out, "loop {{");
+        if repair {
+            try!(self.borrow_stacks());
+        }
         rust!(self.out,
               "let {}state = *{}states.last().unwrap() as usize;",
               self.prefix,
@@ -606,69 +968,160 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
                   self.prefix);
         }
         rust!(self.out, "if {}action < 0 {{", self.prefix);
-        rust!(self.out,
-              "if let Some(r) = {}reduce({}{}action, None, &mut {}states, &mut {}symbols, {}) {{",
-              self.prefix,
-              self.grammar.user_parameter_refs(),
-              self.prefix,
-              self.prefix,
-              self.prefix,
-              phantom_data_expr);
-        rust!(self.out, "return r;");
-        rust!(self.out, "}}");
+        try!(self.reduce("None", true));
         rust!(self.out, "}} else {{");
 
         // EOF error recovery
         try!(self.unrecognized_token_error("None"));
 
         if self.uses_error_recovery() {
-            let extra_test = format!("&& {}EOF_ACTION[({}error_state as usize - 1)] != 0 ",
-                self.prefix,
-                self.prefix);
-            try!(self.error_recovery("None", &extra_test));
-            rust!(self.out, "let {}new_len = {}symbols.len() - ({}original_state_len - {}states.len());",
-                self.prefix,
-                self.prefix,
-                self.prefix,
-                self.prefix);
-            rust!(self.out, "{}symbols.truncate({}new_len);",
-                self.prefix,
-                self.prefix);
-            rust!(self.out, "{}states.push({}error_state - 1);",
-                self.prefix,
-                self.prefix);
-            rust!(self.out, "let {}recovery = {}lalrpop_util::ErrorRecovery {{",
-                self.prefix,
-                self.prefix);
-            rust!(self.out, "error: {}error,", self.prefix);
-            rust!(self.out, "dropped_tokens: Vec::new(),");
-            rust!(self.out, "}};");
-            rust!(self.out,
-                "{}symbols.push(({}last_location.clone(), {}Symbol::Termerror({}recovery), {}last_location.clone()));",
-                self.prefix,
+            // we need a state that can shift `error` and then either
+            // accept the EOF or (with the repair strategy) a token
+            // that we insert in front of it
+            let insertion_test = if repair {
+                format!(" || Self::{}find_repair(&{}states[..], {}error_state - 1, None).is_some()",
+                        self.prefix,
+                        self.prefix,
+                        self.prefix)
+            } else {
+                String::new()
+            };
+            let extra_test = format!("&& ({}EOF_ACTION[({}error_state as usize - 1)] != 0{}) ",
                 self.prefix,
                 self.prefix,
-                self.prefix,
-                self.prefix);
-
+                insertion_test);
+            try!(self.error_recovery("None", &extra_test, true));
+            let location = format!("self.{}last_location.clone()", self.prefix);
+            if repair {
+                rust!(self.out,
+                      "if {}EOF_ACTION[({}error_state as usize - 1)] != 0 {{",
+                      self.prefix,
+                      self.prefix);
+                try!(self.push_error_symbol(&location, &location, "Vec::new()", "None"));
+                rust!(self.out, "}} else {{");
+                rust!(self.out,
+                      "let (_, {}inserted) = \
+                       Self::{}find_repair(&{}states[..], {}error_state - 1, None).unwrap();",
+                      self.prefix,
+                      self.prefix,
+                      self.prefix,
+                      self.prefix);
+                rust!(self.out,
+                      "let {}inserted = ({}, {}inserted, {});",
+                      self.prefix,
+                      location,
+                      self.prefix,
+                      location);
+                let insert = format!("Some({}lalrpop_util::Repair::Insert({}inserted.clone()))",
+                                     self.prefix,
+                                     self.prefix);
+                try!(self.push_error_symbol(&location, &location, "Vec::new()", &insert));
+                rust!(self.out, "try!(self.{}consume({}inserted));", self.prefix, self.prefix);
+                rust!(self.out, "}}");
+            } else {
+                try!(self.push_error_symbol(&location, &location, "Vec::new()", "None"));
+            }
         } else {
             rust!(self.out, "return Err({}error);", self.prefix)
         }
 
         rust!(self.out, "}}"); // else
-        
-        rust!(self.out, "}}"); // while let
 
-        self.end_parser_fn()
+        rust!(self.out, "}}"); // loop
+
+        rust!(self.out, "}}"); // fn
+        Ok(())
     }
 
-    fn next_token(&mut self) -> io::Result<()> {
+    /// Emits `find_repair()`, which returns the first terminal (and a
+    /// token for it) that could be shifted after `error_state` and be
+    /// followed by `next`. Only terminals whose tokens we know how to
+    /// construct are candidates.
+    fn write_parser_find_repair_fn(&mut self) -> io::Result<()> {
         rust!(self.out,
-              "{}lookahead = match {}tokens.next() {{",
+              "fn {}find_repair({}states: &[i32], {}error_state: i32, {}next: Option<usize>) \
+               -> Option<(usize, {})> {{",
+              self.prefix,
+              self.prefix,
+              self.prefix,
+              self.prefix,
+              self.types.terminal_token_type());
+        for (&terminal, index) in self.grammar.terminals.all.iter().zip(0..) {
+            if terminal == TerminalString::Error {
+                continue;
+            }
+            if let Some(token) = self.token_expression(terminal) {
+                rust!(self.out,
+                      "if {}simulate({}states, {}error_state, &[Some({}), {}next]) {{",
+                      self.prefix,
+                      self.prefix,
+                      self.prefix,
+                      index,
+                      self.prefix);
+                rust!(self.out, "return Some(({}, {}));", index, token);
+                rust!(self.out, "}}");
+            }
+        }
+        rust!(self.out, "None");
+        rust!(self.out, "}}");
+        Ok(())
+    }
+
+    /// Returns an expression that constructs a token matching
+    /// `terminal`, if we can: its pattern may not contain `_` or
+    /// `<T>`, except for the text of a quoted literal when we
+    /// generate the tokenizer.
+    fn token_expression(&self, terminal: TerminalString) -> Option<String> {
+        let text = self.grammar.intern_token.as_ref().and_then(|intern_token| {
+            intern_token.literals
+                        .iter()
+                        .find(|&&literal| intern_token.terminal(literal) == terminal)
+                        .and_then(|&literal| match literal {
+                            TerminalLiteral::Quoted(s) => {
+                                Some(intern::read(|interner| format!("{:?}", interner.data(s))))
+                            }
+                            TerminalLiteral::Regex(_) => None,
+                        })
+        });
+        let pattern: &Pattern<TypeRepr> = self.grammar.pattern(terminal);
+        pattern.to_expression(&mut |_| text.clone())
+    }
+
+    fn write_parser_fn(&mut self) -> io::Result<()> {
+        try!(self.start_parser_fn());
+
+        try!(self.define_tokens());
+
+        let parameters: Vec<_> = self.grammar
+                                     .parameters
+                                     .iter()
+                                     .map(|parameter| parameter.name)
+                                     .collect();
+        let type_parameters = self.grammar.non_lifetime_type_parameters();
+        let turbofish = if type_parameters.is_empty() {
+            String::new()
+        } else {
+            format!("::<{}>", Sep(", ", &type_parameters))
+        };
+        rust!(self.out,
+              "let mut {}parser = {}{}::new({});",
+              self.prefix,
+              self.parser_type(),
+              turbofish,
+              Sep(", ", &parameters));
+
+        // Feed each token to the parser, then signal the end of the
+        // input.
+        rust!(self.out, "loop {{");
+        if DEBUG_PRINT {
+            rust!(self.out, "println!(\"outer loop\");");
+        }
+        rust!(self.out,
+              "let {}lookahead = match {}tokens.next() {{",
               self.prefix,
               self.prefix);
         rust!(self.out, "Some(Ok(v)) => v,");
-        rust!(self.out, "None => break '{}shift,", self.prefix); // EOF: break out
+        rust!(self.out, "None => break,");
         if self.grammar.intern_token.is_some() {
             // when we generate the tokenizer, the generated errors are `ParseError` values
             rust!(self.out, "Some(Err(e)) => return Err(e),");
@@ -679,9 +1132,105 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
                   self.prefix);
         }
         rust!(self.out, "}};");
-        rust!(self.out, "{}last_location = {}lookahead.2.clone();",
+        rust!(self.out,
+              "try!({}parser.{}consume({}lookahead));",
+              self.prefix,
               self.prefix,
               self.prefix);
+        rust!(self.out, "}}");
+        rust!(self.out, "{}parser.finish()", self.prefix);
+
+        self.end_parser_fn()
+    }
+
+    /// Binds the parser's stacks to local variables, which is what
+    /// the code emitted by the other routines refers to.
+    fn borrow_stacks(&mut self) -> io::Result<()> {
+        rust!(self.out,
+              "let {}states = &mut self.{}states;",
+              self.prefix,
+              self.prefix);
+        rust!(self.out,
+              "let {}symbols = &mut self.{}symbols;",
+              self.prefix,
+              self.prefix);
+        Ok(())
+    }
+
+    /// Emits a call to `reduce`. Reducing the start symbol accepts
+    /// the input, which should only happen `at_eof`; if it happens
+    /// before, the lookahead is reported as an extra token.
+    fn reduce(&mut self, lookahead_start: &str, at_eof: bool) -> io::Result<()> {
+        let parameters: String = self.grammar
+                                     .parameters
+                                     .iter()
+                                     .map(|parameter| format!("self.{}, ", parameter.name))
+                                     .collect();
+        rust!(self.out,
+              "if let Some(r) = {}reduce({}{}action, {}, {}states, {}symbols, {}) {{",
+              self.prefix,
+              parameters,
+              self.prefix,
+              lookahead_start,
+              self.prefix,
+              self.prefix,
+              self.phantom_data_expr());
+        if at_eof && self.recovery_limit().is_some() {
+            rust!(self.out, "return match r {{");
+            rust!(self.out, "Ok(v) => Ok((self.{}recoveries, v)),", self.prefix);
+            rust!(self.out, "Err(e) => Err(e),");
+            rust!(self.out, "}};");
+        } else if at_eof {
+            rust!(self.out, "return r;");
+        } else {
+            rust!(self.out, "return match r {{");
+            rust!(self.out,
+                  "Ok(_) => Err({}lalrpop_util::ParseError::ExtraToken {{ token: {}lookahead }}),",
+                  self.prefix,
+                  self.prefix);
+            rust!(self.out, "Err(e) => Err(e),");
+            rust!(self.out, "}};");
+        }
+        rust!(self.out, "}}");
+        Ok(())
+    }
+
+    /// Emits code that completes error recovery: the states popped
+    /// by `error_recovery` take their symbols with them, and the
+    /// `error` symbol is shifted in their place.
+    fn push_error_symbol(&mut self,
+                         start: &str,
+                         end: &str,
+                         dropped_tokens: &str,
+                         repair: &str)
+                         -> io::Result<()> {
+        rust!(self.out, "let {}start = {};", self.prefix, start);
+        rust!(self.out, "let {}end = {};", self.prefix, end);
+        rust!(self.out, "let {}new_len = {}symbols.len() - ({}original_state_len - {}states.len());",
+            self.prefix,
+            self.prefix,
+            self.prefix,
+            self.prefix);
+        rust!(self.out, "{}symbols.truncate({}new_len);",
+            self.prefix,
+            self.prefix);
+        rust!(self.out, "{}states.push({}error_state - 1);",
+            self.prefix,
+            self.prefix);
+        rust!(self.out, "let {}recovery = {}lalrpop_util::ErrorRecovery {{",
+            self.prefix,
+            self.prefix);
+        rust!(self.out, "error: {}error,", self.prefix);
+        rust!(self.out, "dropped_tokens: {},", dropped_tokens);
+        rust!(self.out, "repair: {},", repair);
+        rust!(self.out, "}};");
+        rust!(self.out,
+            "{}symbols.push(({}start, {}Symbol::Termerror({}recovery), {}end));",
+            self.prefix,
+            self.prefix,
+            self.prefix,
+            self.prefix,
+            self.prefix);
         Ok(())
     }
 
@@ -1006,8 +1555,13 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         Ok(())
     }
 
-    fn error_recovery(&mut self, lookahead_start: &str, extra_test: &str) -> io::Result<()> {
-        let phantom_data_expr = self.phantom_data_expr();
+    fn error_recovery(&mut self, lookahead_start: &str, extra_test: &str, at_eof: bool) -> io::Result<()> {
+        // Give up once we have recovered as often as we may
+        if let Some(limit) = self.recovery_limit() {
+            rust!(self.out, "if self.{}recoveries == {} {{", self.prefix, limit);
+            rust!(self.out, "return Err({}error);", self.prefix);
+            rust!(self.out, "}}");
+        }
 
         // First perform all reductions from the current state
         rust!(self.out, "loop {{");
@@ -1036,17 +1590,7 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
                   self.prefix);
         }
 
-        rust!(self.out,
-              "if let Some(r) = {}reduce({}{}action, {}, &mut {}states, &mut {}symbols, {}) {{",
-              self.prefix,
-              self.grammar.user_parameter_refs(),
-              self.prefix,
-              lookahead_start,
-              self.prefix,
-              self.prefix,
-              phantom_data_expr);
-        rust!(self.out, "return r;");
-        rust!(self.out, "}}");
+        try!(self.reduce(lookahead_start, at_eof));
         rust!(self.out, "}}");
 
         rust!(self.out, "let {}original_state_len = {}states.len();",
@@ -1082,9 +1626,33 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
 
         rust!(self.out, "}}"); // loop
 
+        if self.recovery_limit().is_some() {
+            rust!(self.out, "self.{}recoveries += 1;", self.prefix);
+        }
+
         Ok(())
     }
 
+    /// The name of the push parser struct, like `ExprParser`.
+    fn parser_type(&self) -> String {
+        format!("{}Parser", self.user_start_symbol)
+    }
+
+    /// Returns a phantom data type that captures all the
+    /// user-declared type parameters, including lifetimes, so that
+    /// the parser struct uses each of them.
+    fn parser_phantom_data_type(&self) -> String {
+        let parameters: Vec<_> = self.grammar
+                                     .type_parameters
+                                     .iter()
+                                     .map(|parameter| match *parameter {
+                                         TypeParameter::Lifetime(_) => format!("&{} ()", parameter),
+                                         TypeParameter::Id(_) => format!("{}", parameter),
+                                     })
+                                     .collect();
+        format!("::std::marker::PhantomData<({})>", Sep(", ", &parameters))
+    }
+
     fn symbol_type(&self) -> String {
         format!("{}Symbol<{}>",
                 self.prefix,
@@ -1096,13 +1664,74 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
         format!("({},{},{})", loc_type, self.symbol_type(), loc_type)
     }
 
-    fn uses_error_recovery(&self) -> bool {
-        self.states.iter().any(|state| {
-            state.shifts.contains_key(&TerminalString::Error) ||
-                state.reductions
-                    .iter()
-                    .any(|&(ref t, _)| t.contains(Token::Terminal(TerminalString::Error)))
-        })
+    /// True if the grammar uses error recovery and asked for the
+    /// `repair` strategy.
+    fn uses_error_repair(&self) -> bool {
+        self.grammar.algorithm.recovery == RecoveryStrategy::Repair && self.uses_error_recovery()
+    }
+
+    /// The value for the `repair` field of an `ErrorRecovery` that
+    /// completes a recovery in which tokens were dropped.
+    fn repair_variable(&self) -> String {
+        if self.uses_error_repair() {
+            format!("{}repair", self.prefix)
+        } else {
+            format!("None")
+        }
+    }
+
+    /// Emits code that looks for a repair once the token following
+    /// the one that caused an error is known (`next`; `None` at EOF):
+    /// deleting the erroneous token, or else substituting another
+    /// token for it. Binds `repair` and `substitution`, the terminal
+    /// and token to parse in place of the erroneous one.
+    fn delete_or_substitute(&mut self, next: &str) -> io::Result<()> {
+        let prefix = self.prefix;
+        rust!(self.out, "let mut {}repair = None;", prefix);
+        rust!(self.out, "let mut {}substitution = None;", prefix);
+        rust!(self.out, "if {}dropped_tokens.len() == 1 {{", prefix);
+        rust!(self.out, "let {}token = {}dropped_tokens[0].clone();", prefix, prefix);
+        rust!(self.out,
+              "if {}simulate(&{}states[..], {}error_state - 1, &[{}]) {{",
+              prefix,
+              prefix,
+              prefix,
+              next);
+        rust!(self.out,
+              "{}repair = Some({}lalrpop_util::Repair::Delete({}token));",
+              prefix,
+              prefix,
+              prefix);
+        rust!(self.out,
+              "}} else if let Some(({}integer1, {}replacement)) = \
+               Self::{}find_repair(&{}states[..], {}error_state - 1, {}) {{",
+              prefix,
+              prefix,
+              prefix,
+              prefix,
+              prefix,
+              next);
+        rust!(self.out,
+              "let {}replacement = ({}token.0.clone(), {}replacement, {}token.2.clone());",
+              prefix,
+              prefix,
+              prefix,
+              prefix);
+        rust!(self.out,
+              "{}repair = Some({}lalrpop_util::Repair::Substitute {{ \
+               token: {}token, replacement: {}replacement.clone() }});",
+              prefix,
+              prefix,
+              prefix,
+              prefix);
+        rust!(self.out,
+              "{}substitution = Some(({}integer1, {}replacement));",
+              prefix,
+              prefix,
+              prefix);
+        rust!(self.out, "}}");
+        rust!(self.out, "}}");
+        Ok(())
     }
 
     fn unrecognized_token_error(&mut self, token: &str) -> io::Result<()> {
@@ -1126,20 +1755,12 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TableDrive
             self.prefix,
             self.prefix);
 
-        rust!(self.out, "const {}TERMINAL: &'static [&'static str] = &[", self.prefix);
-        // Subtract one to exlude the error terminal
-        for &terminal in &self.grammar.terminals.all[..self.grammar.terminals.all.len() - 1] {
-            // Three # should hopefully be enough to prevent any reasonable terminal from escaping the literal
-            rust!(self.out, "r###\"{}\"###,", terminal);
-        }
-        rust!(self.out, "];");
-
         // Grab any terminals in the current state which would have resulted in a successful parse
-        rust!(self.out, "{}ACTION[({}state * {})..].iter().zip({}TERMINAL).filter_map(|(&state, terminal)| {{",
+        rust!(self.out, "{}ACTION[({}state * {})..].iter().zip({}::__metadata::TERMINALS).filter_map(|(&state, terminal)| {{",
             self.prefix,
             self.prefix,
             self.grammar.terminals.all.len(),
-            self.prefix);
+            self.action_module);
         rust!(self.out, "if state == 0 {{");
         rust!(self.out, "None");
         rust!(self.out, "}} else {{");
@@ -69,12 +69,19 @@ impl<'ascent, 'grammar, W: Write> CodeGenerator<'ascent, 'grammar, W, TestAll> {
                                              "super::super::super",
                                              this.out));
             rust!(this.out,
-                  "pub use self::{}parse{}::parse_{};",
+                  "pub use self::{}parse{}::{{parse_{}, {}Parser}};",
                   this.prefix,
                   this.start_symbol,
+                  this.user_start_symbol,
                   this.user_start_symbol);
             rust!(this.out, "}}");
 
+            // the push parser is only available table-driven
+            rust!(this.out,
+                  "pub use self::{}parse_table::{}Parser;",
+                  this.prefix,
+                  this.user_start_symbol);
+
             Ok(())
         })
     }
@@ -22,6 +22,22 @@ pub fn report_error(grammar: &Grammar,
     cx.report_errors()
 }
 
+/// Classifies each conflict the way `report_error` does, returning
+/// a one-line description of each (for the state report).
+pub fn describe_conflicts<'grammar>(grammar: &'grammar Grammar,
+                                    error: &LR1TableConstructionError<'grammar>)
+                                    -> Vec<(TokenConflict<'grammar>, String)>
+{
+    let mut cx = ErrorReportingCx::new(grammar, &error.states, &error.conflicts);
+    token_conflicts(&error.conflicts)
+        .into_iter()
+        .map(|conflict| {
+            let description = cx.classify(&conflict).description();
+            (conflict, description)
+        })
+        .collect()
+}
+
 struct ErrorReportingCx<'cx, 'grammar: 'cx> {
     grammar: &'grammar Grammar,
     first_sets: FirstSets,
@@ -61,7 +77,36 @@ enum ConflictClassification {
     Naive,
 }
 
-type TokenConflict<'grammar> = Conflict<'grammar, Token>;
+pub type TokenConflict<'grammar> = Conflict<'grammar, Token>;
+
+impl ConflictClassification {
+    fn description(&self) -> String {
+        match *self {
+            ConflictClassification::Ambiguity { .. } => {
+                format!("ambiguous grammar")
+            }
+            ConflictClassification::Precedence { nonterminal, .. } => {
+                format!("ambiguous grammar; looks like a precedence error related to `{}`",
+                        nonterminal)
+            }
+            ConflictClassification::SuggestInline { nonterminal, .. } => {
+                format!("local ambiguity; could be resolved with `#[inline]` on `{}`",
+                        nonterminal)
+            }
+            ConflictClassification::SuggestQuestion { nonterminal, symbol, .. } => {
+                format!("local ambiguity; could be resolved by replacing `{}` with `{}?`",
+                        nonterminal,
+                        symbol)
+            }
+            ConflictClassification::InsufficientLookahead { .. } => {
+                format!("local ambiguity")
+            }
+            ConflictClassification::Naive => {
+                format!("conflict (not classified)")
+            }
+        }
+    }
+}
 
 impl<'cx, 'grammar> ErrorReportingCx<'cx, 'grammar> {
     fn new(grammar: &'grammar Grammar,
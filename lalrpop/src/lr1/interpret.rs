@@ -1,14 +1,23 @@
-//! LR(1) interpeter. Just builds up parse trees. Intended for testing.
+//! LR(1) interpeter. Just builds up parse trees. Used for testing,
+//! and to try out grammars without generating code (see
+//! `build::interpret`).
 
 use lr1::core::*;
 use lr1::lookahead::*;
-use generate::ParseTree;
 use grammar::repr::*;
 use std::iter::IntoIterator;
 use std::fmt::{Debug, Display, Formatter, Error};
 use util::Sep;
 
-pub type InterpretError<'grammar, L> = (&'grammar State<'grammar, L>, Token);
+#[derive(PartialEq, Eq)]
+pub enum ParseTree {
+    Nonterminal(NonterminalString, Vec<ParseTree>),
+    Terminal(TerminalString),
+}
+
+/// The state in which the parse got stuck, the number of tokens
+/// shifted before that, and the token it could not handle.
+pub type InterpretError<'grammar, L> = (&'grammar State<'grammar, L>, usize, Token);
 
 /// Feed in the given tokens and then EOF, returning the final parse tree that is reduced.
 pub fn interpret<'grammar, TOKENS, L>(states: &'grammar [State<'grammar, L>],
@@ -38,6 +47,7 @@ struct Machine<'grammar, L: LookaheadInterpret + 'grammar> {
     states: &'grammar [State<'grammar, L>],
     state_stack: Vec<StateIndex>,
     data_stack: Vec<ParseTree>,
+    shifted: usize,
 }
 
 impl<'grammar, L> Machine<'grammar, L>
@@ -49,6 +59,7 @@ impl<'grammar, L> Machine<'grammar, L>
             states: states,
             state_stack: vec![],
             data_stack: vec![],
+            shifted: 0,
         }
     }
 
@@ -71,19 +82,17 @@ impl<'grammar, L> Machine<'grammar, L>
         while let Some(terminal) = token {
             let state = self.top_state();
 
-            println!("state={:?}", state);
-            println!("terminal={:?}", terminal);
-
             // check whether we can shift this token
             if let Some(&next_index) = state.shifts.get(&terminal) {
                 self.data_stack.push(ParseTree::Terminal(terminal));
                 self.state_stack.push(next_index);
+                self.shifted += 1;
                 token = tokens.next();
             } else if let Some(production) = L::reduction(state, Token::Terminal(terminal)) {
                 let more = self.reduce(production);
                 assert!(more);
             } else {
-                return Err((state, Token::Terminal(terminal)));
+                return Err((state, self.shifted, Token::Terminal(terminal)));
             }
         }
 
@@ -100,7 +109,7 @@ impl<'grammar, L> Machine<'grammar, L>
             let state = self.top_state();
             match L::reduction(state, Token::EOF) {
                 None => {
-                    return Err((state, Token::EOF));
+                    return Err((state, self.shifted, Token::EOF));
                 }
                 Some(production) => {
                     if !self.reduce(production) {
@@ -113,8 +122,6 @@ impl<'grammar, L> Machine<'grammar, L>
     }
 
     fn reduce(&mut self, production: &Production) -> bool {
-        println!("reduce={:?}", production);
-
         let args = production.symbols.len();
 
         // remove the top N items from the data stack
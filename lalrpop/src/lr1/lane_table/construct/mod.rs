@@ -6,10 +6,11 @@ use grammar::repr::*;
 use lr1::build;
 use lr1::core::*;
 use lr1::first::FirstSets;
-use lr1::lookahead::{Lookahead, TokenSet};
+use lr1::lookahead::{Lookahead, Token, TokenSet};
 use lr1::lane_table::lane::LaneTracer;
 use lr1::lane_table::table::{ConflictIndex, LaneTable};
 use lr1::lane_table::table::context_set::OverlappingLookahead;
+use lr1::precedence;
 use lr1::state_graph::StateGraph;
 use std::rc::Rc;
 
@@ -39,7 +40,11 @@ impl<'grammar> LaneTableConstruct<'grammar> {
         let TableConstructionError { states, conflicts: _ } = {
             match build::build_lr0_states(self.grammar, self.start) {
                 // This is the easy (and very rare...) case.
-                Ok(lr0) => return Ok(self.promote_lr0_states(lr0)),
+                Ok(lr0) => {
+                    let mut states = self.promote_lr0_states(lr0);
+                    self.narrow_lookaheads(&mut states);
+                    return Ok(states);
+                }
                 Err(err) => err,
             }
         };
@@ -49,6 +54,7 @@ impl<'grammar> LaneTableConstruct<'grammar> {
 
         // For each inconsistent state, apply the lane-table algorithm to
         // resolve it.
+        let mut conflicts: Vec<Conflict<'grammar, TokenSet>> = vec![];
         for i in 0.. {
             if i >= states.len() {
                 break;
@@ -57,27 +63,59 @@ impl<'grammar> LaneTableConstruct<'grammar> {
             match self.resolve_inconsistencies(&mut states, StateIndex(i)) {
                 Ok(()) => { }
                 Err(_) => {
-                    // We failed because of irreconcilable conflicts
-                    // somewhere. Just compute the conflicts from the final set of
-                    // states.
-                    let conflicts: Vec<Conflict<'grammar, TokenSet>> =
-                        states.iter()
-                              .flat_map(|s| Lookahead::conflicts(&s))
-                              .collect();
-                    return Err(TableConstructionError { states: states,
-                                                        conflicts: conflicts });
+                    // The conflict is irreconcilable: the lookaheads
+                    // overlap in some context. Settle for the LALR(1)
+                    // lookahead of this state and let the precedence
+                    // annotations have a go at what remains; anything
+                    // still conflicting is reported once we have
+                    // looked at the other states.
+                    self.fall_back_to_lalr(&mut states, StateIndex(i));
+                    precedence::resolve_conflicts(&mut states[i]);
+                    conflicts.extend(Lookahead::conflicts(&states[i]));
                 }
             }
         }
 
+        if !conflicts.is_empty() {
+            return Err(TableConstructionError { states: states,
+                                                conflicts: conflicts });
+        }
+
+        self.narrow_lookaheads(&mut states);
         Ok(states)
     }
 
+    /// The reductions of states that were never inconsistent still
+    /// have every token as their lookahead. That is enough to parse
+    /// correctly, but errors are only detected in a later state, and
+    /// the tokens expected in these states (as listed in parse errors,
+    /// the grammar metadata or repair suggestions) would be every
+    /// token. So trace out the lookahead of those reductions as well,
+    /// which, there being nothing else to do in such a state, is their
+    /// LALR(1) lookahead in the final state graph.
+    fn narrow_lookaheads(&self, states: &mut Vec<LR1State<'grammar>>) {
+        let all = self.all_tokens();
+        let state_graph = StateGraph::new(states);
+        for i in 0..states.len() {
+            let actions: Set<Action<'grammar>> = {
+                let state = &states[i];
+                if state.reductions.is_empty() ||
+                   state.reductions.iter().any(|&(ref lookahead, _)| *lookahead != all) {
+                    continue;
+                }
+                state.reductions.iter().map(|&(_, p)| Action::Reduce(p)).collect()
+            };
+            let table = self.trace_lanes(states, &state_graph, StateIndex(i), &actions);
+            table.overlapping_columns().apply(&mut states[i], &actions);
+        }
+    }
+
     /// Given a set of LR0 states, returns LR1 states where the lookahead
-    /// is always `TokenSet::all()`. We refer to these states as LR(0-1)
+    /// is always every token. We refer to these states as LR(0-1)
     /// states in the README.
     fn promote_lr0_states(&self, lr0: Vec<LR0State<'grammar>>) -> Vec<LR1State<'grammar>> {
-        let all = TokenSet::all();
+        let all = self.all_tokens();
+        let accept = self.accept_lookahead(&all);
         lr0.into_iter()
             .map(|s| {
                 let items = s.items
@@ -93,7 +131,13 @@ impl<'grammar> LaneTableConstruct<'grammar> {
                     .collect();
                 let reductions = s.reductions
                     .into_iter()
-                    .map(|(_, p)| (all.clone(), p))
+                    .map(|(_, p)| {
+                        if p.nonterminal == self.start {
+                            (accept.clone(), p)
+                        } else {
+                            (all.clone(), p)
+                        }
+                    })
                     .collect();
                 State {
                     index: s.index,
@@ -106,6 +150,43 @@ impl<'grammar> LaneTableConstruct<'grammar> {
             .collect()
     }
 
+    /// The lookahead with which we start out for reductions of the
+    /// start nonterminal. Those accept the input, so we cannot let
+    /// them happen before EOF, even in a state that never turns out to
+    /// be inconsistent. (The synthetic start symbols used by the code
+    /// generators never appear elsewhere in the grammar, so EOF is
+    /// their only lookahead.)
+    fn accept_lookahead(&self, all: &TokenSet) -> TokenSet {
+        let start = Symbol::Nonterminal(self.start);
+        let start_is_recursive =
+            self.grammar.nonterminals
+                        .values()
+                        .flat_map(|data| &data.productions)
+                        .any(|production| production.symbols.contains(&start));
+        if start_is_recursive {
+            all.clone()
+        } else {
+            TokenSet::eof()
+        }
+    }
+
+    /// Returns `TokenSet::all()`, less the error-recovery terminal if
+    /// the grammar never mentions it; otherwise the code generators
+    /// would think that the grammar uses error recovery.
+    fn all_tokens(&self) -> TokenSet {
+        let mut all = TokenSet::all();
+        let error = TerminalString::Error;
+        let uses_error_recovery =
+            self.grammar.nonterminals
+                        .values()
+                        .flat_map(|data| &data.productions)
+                        .any(|production| production.symbols.contains(&Symbol::Terminal(error)));
+        if !uses_error_recovery {
+            all.remove(Token::Terminal(error));
+        }
+        all
+    }
+
     fn resolve_inconsistencies(&self,
                                states: &mut Vec<LR1State<'grammar>>,
                                inconsistent_state: StateIndex)
@@ -153,6 +234,14 @@ impl<'grammar> LaneTableConstruct<'grammar> {
         Ok(())
     }
 
+    fn fall_back_to_lalr(&self,
+                         states: &mut Vec<LR1State<'grammar>>,
+                         inconsistent_state: StateIndex) {
+        let actions = super::conflicting_actions(&states[inconsistent_state.0]);
+        let table = self.build_lane_table(states, inconsistent_state, &actions);
+        table.overlapping_columns().apply(&mut states[inconsistent_state.0], &actions);
+    }
+
     fn attempt_lalr(&self,
                     state: &mut LR1State<'grammar>,
                     table: &LaneTable<'grammar>,
@@ -177,10 +266,20 @@ impl<'grammar> LaneTableConstruct<'grammar> {
                         actions: &Set<Action<'grammar>>)
                         -> LaneTable<'grammar> {
         let state_graph = StateGraph::new(states);
+        self.trace_lanes(states, &state_graph, inconsistent_state, actions)
+    }
+
+    fn trace_lanes(&self,
+                   states: &[LR1State<'grammar>],
+                   state_graph: &StateGraph,
+                   inconsistent_state: StateIndex,
+                   actions: &Set<Action<'grammar>>)
+                   -> LaneTable<'grammar> {
         let mut tracer = LaneTracer::new(self.grammar,
                                          states,
                                          &self.first_sets,
-                                         &state_graph,
+                                         state_graph,
+                                         self.start,
                                          actions.len());
         for (i, &action) in actions.iter().enumerate() {
             tracer.start_trace(inconsistent_state, ConflictIndex::new(i), action);
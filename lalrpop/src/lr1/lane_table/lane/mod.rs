@@ -14,6 +14,7 @@ pub struct LaneTracer<'trace, 'grammar: 'trace, L: Lookahead + 'trace> {
     states: &'trace [State<'grammar, L>],
     first_sets: &'trace FirstSets,
     state_graph: &'trace StateGraph,
+    start_nt: NonterminalString,
     table: LaneTable<'grammar>,
 }
 
@@ -22,12 +23,14 @@ impl<'trace, 'grammar, L: Lookahead> LaneTracer<'trace, 'grammar, L> {
                states: &'trace [State<'grammar, L>],
                first_sets: &'trace FirstSets,
                state_graph: &'trace StateGraph,
+               start_nt: NonterminalString,
                conflicts: usize)
                -> Self {
         LaneTracer {
             states: states,
             first_sets: first_sets,
             state_graph: state_graph,
+            start_nt: start_nt,
             table: LaneTable::new(grammar, conflicts),
         }
     }
@@ -112,6 +115,14 @@ impl<'trace, 'grammar, L: Lookahead> LaneTracer<'trace, 'grammar, L> {
 
         let state_items = &self.states[state.0].items.vec;
         let nonterminal = item.production.nonterminal;
+
+        // The start state has no item to unepsilon into for the
+        // start nonterminal itself; the context there is the end of
+        // the input.
+        if state == StateIndex(0) && nonterminal == self.start_nt {
+            self.table.add_lookahead(state, conflict, &TokenSet::eof());
+        }
+
         for pred_item in state_items.iter()
                                     .filter(|i| i.can_shift_nonterminal(nonterminal)) {
             let symbol_sets = pred_item.symbol_sets();
@@ -2,6 +2,7 @@ use collections::Set;
 use lr1::core::*;
 use lr1::lookahead::Lookahead;
 use grammar::repr::*;
+use tls::Tls;
 
 mod construct;
 mod lane;
@@ -13,7 +14,13 @@ mod test;
 pub fn build_lane_table_states<'grammar>(grammar: &'grammar Grammar,
                                          start: NonterminalString)
                                          -> LR1Result<'grammar> {
-    construct::LaneTableConstruct::new(grammar, start).construct()
+    profile! {
+        &Tls::session(),
+        "LR(1) state construction (lane table)",
+        {
+            construct::LaneTableConstruct::new(grammar, start).construct()
+        }
+    }
 }
 
 fn conflicting_actions<'grammar, L: Lookahead>(state: &State<'grammar, L>)
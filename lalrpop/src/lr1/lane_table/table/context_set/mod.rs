@@ -81,6 +81,12 @@ impl ContextSet {
         Ok(self.values[conflict.index].union_with(&set))
     }
 
+    /// Like `insert`, but merges `set` into `conflict` even if it
+    /// overlaps the lookahead of other conflicts.
+    pub fn insert_overlapping(&mut self, conflict: ConflictIndex, set: &TokenSet) {
+        self.values[conflict.index].union_with(&set);
+    }
+
     pub fn apply<'grammar>(&self,
                            state: &mut LR1State<'grammar>,
                            actions: &Set<Action<'grammar>>) {
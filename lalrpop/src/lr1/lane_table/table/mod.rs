@@ -80,6 +80,18 @@ impl<'grammar> LaneTable<'grammar> {
         Ok(columns)
     }
 
+    /// Like `columns`, but tolerates overlapping lookahead. The
+    /// result is the LALR(1) lookahead of each conflict, which may
+    /// still contain conflicts; it is used once we have given up on
+    /// splitting the inconsistent state.
+    pub fn overlapping_columns(&self) -> ContextSet {
+        let mut columns = ContextSet::new(self.conflicts);
+        for (&(_, conflict_index), set) in &self.lookaheads {
+            columns.insert_overlapping(conflict_index, set);
+        }
+        columns
+    }
+
     pub fn successors(&self, state: StateIndex) -> Option<&Set<StateIndex>> {
         self.successors.get(&state)
     }
@@ -129,11 +141,14 @@ impl<'grammar> LaneTable<'grammar> {
         }
 
         // In some cases, there are states that have no context at
-        // all, only successors. In that case, make sure to add an
-        // empty row for them.
-        for (&state_index, _) in &self.successors {
-            map.entry(state_index)
-                .or_insert_with(|| ContextSet::new(self.conflicts));
+        // all, only successors (or, like the inconsistent state in a
+        // reduce/reduce conflict, only predecessors). In that case,
+        // make sure to add an empty row for them.
+        for (&state_index, successors) in &self.successors {
+            for &state_index in Some(&state_index).into_iter().chain(successors) {
+                map.entry(state_index)
+                    .or_insert_with(|| ContextSet::new(self.conflicts));
+            }
         }
 
         Ok(map)
@@ -2,6 +2,8 @@ use intern::intern;
 use grammar::repr::*;
 use test_util::{expect_debug, normalized_grammar};
 use lr1::build;
+use lr1::build_lalr::build_lalr_states;
+use lr1::expected_terminals;
 use lr1::core::*;
 use lr1::first::FirstSets;
 use lr1::interpret;
@@ -120,6 +122,7 @@ fn build_table<'grammar>(grammar: &'grammar Grammar,
                                      &lr0_err.states,
                                      &first_sets,
                                      &state_graph,
+                                     nt(goal),
                                      conflicting_items.len());
     for (i, &conflicting_item) in conflicting_items.iter().enumerate() {
         tracer.start_trace(inconsistent_state.index,
@@ -197,6 +200,64 @@ fn paper_example_g0_build() {
     interpret::interpret(&states, tokens!["e", "e", "e"]).unwrap_err();
 }
 
+/// The only context for reducing `X` is the end of the input, which
+/// the lanes traced back to the start state must account for.
+#[test]
+fn eof_context_build() {
+    let _tls = Tls::test();
+    let grammar = normalized_grammar(r#"
+grammar;
+
+pub G: () = {
+    X,
+    Y "c",
+};
+
+X: () = "e";
+
+Y: () = "e";
+"#);
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+    let states = LaneTableConstruct::new(&grammar, nt("G")).construct()
+        .expect("failed to build lane table states");
+
+    let tree = interpret::interpret(&states, tokens!["e"]).unwrap();
+    expect_debug(&tree, r#"[G: [X: "e"]]"#);
+
+    let tree = interpret::interpret(&states, tokens!["e", "c"]).unwrap();
+    expect_debug(&tree, r#"[G: [Y: "e"], "c"]"#);
+}
+
+/// Like G1, but the inconsistent state has only the two reductions,
+/// so it shows up in the lane table as a successor only.
+#[test]
+fn reduce_reduce_build() {
+    let _tls = Tls::test();
+    let grammar = normalized_grammar(r#"
+grammar;
+
+pub G: () = {
+    "a" X "d",
+    "a" Y "c",
+    "b" X "c",
+    "b" Y "d",
+};
+
+X: () = "e";
+
+Y: () = "e";
+"#);
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+    let states = LaneTableConstruct::new(&grammar, nt("G")).construct()
+        .expect("failed to build lane table states");
+
+    let tree = interpret::interpret(&states, tokens!["a", "e", "c"]).unwrap();
+    expect_debug(&tree, r#"[G: "a", [Y: "e"], "c"]"#);
+
+    let tree = interpret::interpret(&states, tokens!["b", "e", "c"]).unwrap();
+    expect_debug(&tree, r#"[G: "b", [X: "e"], "c"]"#);
+}
+
 #[test]
 fn paper_example_g1_build() {
     let _tls = Tls::test();
@@ -349,3 +410,120 @@ fn paper_example_large_build() {
     expect_debug(&tree, r#"[G: "y", [W: [U: "s"], [X: "k", "t"], [C: "c"]], "b"]"#);
 }
 
+
+/// States that the lane table never has to split still get precise
+/// lookahead, so the tokens expected after each prefix of the input
+/// are the same as with LALR(1), for a grammar that is LALR(1).
+#[test]
+fn expected_terminals_match_lalr() {
+    let _tls = Tls::test();
+    let grammar = normalized_grammar(r#"
+grammar;
+
+pub E: () = {
+    E "+" T,
+    T,
+};
+
+T: () = {
+    T "*" F,
+    F,
+};
+
+F: () = {
+    "(" E ")",
+    "N",
+};
+"#);
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+    let lalr_states = build_lalr_states(&grammar, nt("E")).unwrap();
+    let lane_states = LaneTableConstruct::new(&grammar, nt("E")).construct().unwrap();
+
+    let input = ["(", "N", "*", "N", "+", "N", ")", "*", "N"];
+    for len in 0..input.len() + 1 {
+        let expected = |states: &[LR1State]| {
+            let tokens = input[..len].iter().map(|&t| term(t));
+            let stack = interpret::interpret_partial(states, tokens).unwrap();
+            expected_terminals(&grammar, &states[stack.last().unwrap().0])
+        };
+        assert_eq!(expected(&lalr_states), expected(&lane_states),
+                   "after {:?}", &input[..len]);
+    }
+}
+
+/// The LR(0) core of a state: its items without their lookahead.
+fn core<'grammar>(state: &LR1State<'grammar>) -> Vec<(*const Production, usize)> {
+    let mut core: Vec<_> = state.items
+        .vec
+        .iter()
+        .map(|item| (item.production as *const Production, item.index))
+        .collect();
+    core.sort();
+    core
+}
+
+/// Checks the lookahead of every reduction in `lane_states` against the
+/// reduction of the same production in the LALR(1) state with the same
+/// core: it is the same if the core was never split, and a subset of it
+/// otherwise.
+fn check_lookaheads_against_lalr<'grammar>(grammar: &'grammar Grammar, start: &str) {
+    // G1 is not LALR(1), but its conflicting states are still built
+    let lalr_states = match build_lalr_states(grammar, nt(start)) {
+        Ok(states) => states,
+        Err(err) => err.states,
+    };
+    let lane_states = LaneTableConstruct::new(grammar, nt(start)).construct().unwrap();
+    for lane_state in &lane_states {
+        let lane_core = core(lane_state);
+        let lalr_state = lalr_states.iter().find(|s| core(s) == lane_core).unwrap();
+        let split = lane_states.iter().filter(|s| core(s) == lane_core).count() > 1;
+        for &(ref lookahead, production) in &lane_state.reductions {
+            let &(ref lalr_lookahead, _) = lalr_state.reductions
+                .iter()
+                .find(|&&(_, p)| p == production)
+                .unwrap();
+            if split {
+                assert_eq!(lookahead.intersection(lalr_lookahead), *lookahead,
+                           "{:?} in {:?}", production, lane_state.index);
+            } else {
+                assert_eq!(lookahead, lalr_lookahead,
+                           "{:?} in {:?}", production, lane_state.index);
+            }
+        }
+    }
+}
+
+/// The lookahead of reductions in states that were never inconsistent
+/// is narrowed down from every token to the LALR(1) lookahead.
+#[test]
+fn narrowed_lookaheads_match_lalr() {
+    let _tls = Tls::test();
+    let grammar = paper_example_g1();
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+    check_lookaheads_against_lalr(&grammar, "G");
+}
+
+#[test]
+fn narrowed_lookaheads_match_lalr_expr() {
+    let _tls = Tls::test();
+    let grammar = normalized_grammar(r#"
+grammar;
+
+pub E: () = {
+    E "+" T,
+    T,
+};
+
+T: () = {
+    T "*" F,
+    F,
+};
+
+F: () = {
+    "(" E ")",
+    "N",
+};
+"#);
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+    check_lookaheads_against_lalr(&grammar, "E");
+}
@@ -198,6 +198,11 @@ impl TokenSet {
         self.bit_set.insert(bit)
     }
 
+    pub fn remove(&mut self, lookahead: Token) -> bool {
+        let bit = self.bit(lookahead);
+        self.bit_set.remove(bit)
+    }
+
     pub fn insert_eof(&mut self) -> bool {
         let bit = self.eof_bit();
         self.bit_set.insert(bit)
@@ -9,25 +9,47 @@ mod core;
 mod error;
 mod example;
 mod first;
+mod interpret;
 mod lane_table;
 mod lookahead;
+mod precedence;
+pub mod report;
 mod state_graph;
 mod tls;
 mod trace;
 
-#[cfg(test)] mod interpret;
-
-pub use self::core::{LR1Result, LR1TableConstructionError};
+pub use self::core::{LR1Result, LR1State, LR1TableConstructionError};
 pub use self::error::report_error;
+pub use self::interpret::{interpret, interpret_partial, ParseTree};
+pub use self::state_graph::StateGraph;
 pub use self::tls::Lr1Tls;
 
 pub fn build_states<'grammar>(grammar: &'grammar Grammar,
                               start: NonterminalString)
                               -> LR1Result<'grammar> {
-    if !grammar.algorithm.lalr {
-        build::build_lr1_states(grammar, start)
-    } else {
+    if grammar.algorithm.lalr {
         build_lalr::build_lalr_states(grammar, start)
+    } else if grammar.algorithm.lane_table {
+        lane_table::build_lane_table_states(grammar, start)
+    } else {
+        build::build_lr1_states(grammar, start)
     }
 }
 
+/// Returns the terminals on which `state` has some action (that is,
+/// the ones that would not be a syntax error there), excluding the
+/// `error` terminal.
+pub fn expected_terminals(grammar: &Grammar, state: &LR1State) -> Vec<TerminalString> {
+    grammar.terminals
+           .all
+           .iter()
+           .cloned()
+           .filter(|&terminal| terminal != TerminalString::Error)
+           .filter(|&terminal| {
+               state.shifts.contains_key(&terminal) ||
+                   state.reductions
+                        .iter()
+                        .any(|&(ref tokens, _)| tokens.contains(lookahead::Token::Terminal(terminal)))
+           })
+           .collect()
+}
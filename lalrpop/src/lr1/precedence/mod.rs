@@ -0,0 +1,113 @@
+//! Resolution of shift/reduce conflicts using the precedence and
+//! associativity that the user declared on alternatives with
+//! `#[precedence(level="N")]` and `#[assoc(side="..")]`.
+//!
+//! Consider a state like this one:
+//!
+//! ```
+//! Expr = Expr "+" Expr (*) ["*", "+", EOF]
+//! Expr = Expr (*) "*" Expr
+//! Expr = Expr (*) "+" Expr
+//! ```
+//!
+//! On `"*"`, we can either reduce the `+` production or shift the
+//! `*` production. We compare the levels of the two productions:
+//! the one with the lower level binds more tightly and wins. On `"+"`
+//! the levels are equal, so we consult the associativity: `left`
+//! favors the reduce, `right` the shift, and `none` makes the
+//! combination a syntax error. Conflicts where any production
+//! involved lacks an annotation are left alone, so that they get
+//! reported as usual.
+
+use grammar::repr::*;
+use lr1::core::*;
+use lr1::lookahead::Token;
+
+#[cfg(test)]
+mod test;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Resolution {
+    Shift,
+    Reduce,
+    Error,
+}
+
+pub fn resolve_conflicts<'grammar>(state: &mut LR1State<'grammar>) {
+    let terminals: Vec<TerminalString> = state.shifts.keys().cloned().collect();
+    for terminal in terminals {
+        let token = Token::Terminal(terminal);
+
+        let mut reductions =
+            state.reductions
+                 .iter()
+                 .enumerate()
+                 .filter(|&(_, &(ref tokens, _))| tokens.contains(token));
+        let (reduce_index, reduce_production) = match (reductions.next(), reductions.next()) {
+            (Some((index, &(_, production))), None) => (index, production),
+            _ => continue, // no conflict, or a reduce/reduce conflict as well
+        };
+
+        let resolution = match resolution(state, terminal, reduce_production) {
+            Some(r) => r,
+            None => continue,
+        };
+
+        if resolution != Resolution::Shift {
+            state.shifts.remove(&terminal);
+        }
+        if resolution != Resolution::Reduce {
+            state.reductions[reduce_index].0.remove(token);
+        }
+    }
+
+    state.reductions.retain(|&(ref tokens, _)| tokens.len() > 0);
+}
+
+/// Decides between reducing `reduce_production` and shifting
+/// `terminal`; returns `None` if the annotations do not settle it.
+fn resolution<'grammar>(state: &LR1State<'grammar>,
+                        terminal: TerminalString,
+                        reduce_production: &'grammar Production)
+                        -> Option<Resolution> {
+    let reduce_precedence = match reduce_production.precedence {
+        Some(p) => p,
+        None => return None,
+    };
+
+    let mut result = None;
+    for item in state.items.vec.iter().filter(|item| item.can_shift_terminal(terminal)) {
+        let shift_production = item.production;
+
+        // precedence levels are only meaningful amongst the
+        // alternatives of a single nonterminal
+        if shift_production.nonterminal != reduce_production.nonterminal {
+            return None;
+        }
+
+        let shift_precedence = match shift_production.precedence {
+            Some(p) => p,
+            None => return None,
+        };
+
+        let r = if reduce_precedence.level < shift_precedence.level {
+            Resolution::Reduce
+        } else if reduce_precedence.level > shift_precedence.level {
+            Resolution::Shift
+        } else {
+            match reduce_precedence.assoc.or(shift_precedence.assoc) {
+                Some(Assoc::Left) => Resolution::Reduce,
+                Some(Assoc::Right) => Resolution::Shift,
+                Some(Assoc::None) => Resolution::Error,
+                None => return None,
+            }
+        };
+
+        if result.unwrap_or(r) != r {
+            return None;
+        }
+        result = Some(r);
+    }
+
+    result
+}
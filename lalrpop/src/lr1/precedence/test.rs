@@ -0,0 +1,199 @@
+use intern::intern;
+use grammar::repr::*;
+use test_util::normalized_grammar;
+use lr1::build::build_lr1_states;
+use lr1::build_lalr::build_lalr_states;
+use lr1::lane_table::build_lane_table_states;
+use lr1::interpret::interpret;
+use lr1::tls::Lr1Tls;
+use tls::Tls;
+
+fn nt(t: &str) -> NonterminalString {
+    NonterminalString(intern(t))
+}
+
+macro_rules! tokens {
+    ($($x:expr),*) => {
+        vec![$(TerminalString::quoted(intern($x))),*].into_iter()
+    }
+}
+
+const EXPR_GRAMMAR: &'static str = r#"
+grammar;
+    extern { enum Tok { "+" => .., "*" => .., "^" => .., "-" => .., "N" => .. } }
+
+    S: () = E;
+
+    E: () = {
+        #[precedence(level="0")]
+        "N",
+
+        #[precedence(level="1")]
+        "-" E,
+
+        #[precedence(level="2")] #[assoc(side="right")]
+        E "^" E,
+
+        #[precedence(level="3")] #[assoc(side="left")]
+        E "*" E,
+
+        #[precedence(level="4")] #[assoc(side="left")]
+        E "+" E,
+    };
+"#;
+
+#[test]
+fn expr_precedence() {
+    let _tls = Tls::test();
+    let grammar = normalized_grammar(EXPR_GRAMMAR);
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+
+    let states = build_lr1_states(&grammar, nt("S")).unwrap();
+
+    // `*` binds more tightly than `+`
+    let tree = interpret(&states, tokens!["N", "+", "N", "*", "N"]).unwrap();
+    assert_eq!(
+        &format!("{}", tree)[..],
+        r#"[S: [E: [E: "N"], "+", [E: [E: "N"], "*", [E: "N"]]]]"#);
+
+    // `+` is left-associative
+    let tree = interpret(&states, tokens!["N", "+", "N", "+", "N"]).unwrap();
+    assert_eq!(
+        &format!("{}", tree)[..],
+        r#"[S: [E: [E: [E: "N"], "+", [E: "N"]], "+", [E: "N"]]]"#);
+
+    // `^` is right-associative
+    let tree = interpret(&states, tokens!["N", "^", "N", "^", "N"]).unwrap();
+    assert_eq!(
+        &format!("{}", tree)[..],
+        r#"[S: [E: [E: "N"], "^", [E: [E: "N"], "^", [E: "N"]]]]"#);
+
+    // prefix `-` binds more tightly than everything else
+    let tree = interpret(&states, tokens!["-", "N", "*", "N"]).unwrap();
+    assert_eq!(
+        &format!("{}", tree)[..],
+        r#"[S: [E: [E: "-", [E: "N"]], "*", [E: "N"]]]"#);
+}
+
+#[test]
+fn expr_precedence_lalr() {
+    let _tls = Tls::test();
+    let grammar = normalized_grammar(EXPR_GRAMMAR);
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+
+    let states = build_lalr_states(&grammar, nt("S")).unwrap();
+
+    let tree = interpret(&states, tokens!["N", "*", "N", "+", "N", "*", "N"]).unwrap();
+    assert_eq!(
+        &format!("{}", tree)[..],
+        r#"[S: [E: [E: [E: "N"], "*", [E: "N"]], "+", [E: [E: "N"], "*", [E: "N"]]]]"#);
+}
+
+#[test]
+fn expr_precedence_lane_table() {
+    let _tls = Tls::test();
+    let grammar = normalized_grammar(EXPR_GRAMMAR);
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+
+    let states = build_lane_table_states(&grammar, nt("S")).unwrap();
+
+    let tree = interpret(&states, tokens!["N", "*", "N", "+", "N", "*", "N"]).unwrap();
+    assert_eq!(
+        &format!("{}", tree)[..],
+        r#"[S: [E: [E: [E: "N"], "*", [E: "N"]], "+", [E: [E: "N"], "*", [E: "N"]]]]"#);
+
+    let tree = interpret(&states, tokens!["N", "^", "N", "^", "N"]).unwrap();
+    assert_eq!(
+        &format!("{}", tree)[..],
+        r#"[S: [E: [E: "N"], "^", [E: [E: "N"], "^", [E: "N"]]]]"#);
+}
+
+#[test]
+fn nonassoc() {
+    let _tls = Tls::test();
+    let grammar = normalized_grammar(r#"
+grammar;
+    extern { enum Tok { "<" => .., "N" => .. } }
+
+    S: () = E;
+
+    E: () = {
+        #[precedence(level="0")]
+        "N",
+
+        #[precedence(level="1")] #[assoc(side="none")]
+        E "<" E,
+    };
+"#);
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+
+    let states = build_lr1_states(&grammar, nt("S")).unwrap();
+    assert!(interpret(&states, tokens!["N", "<", "N"]).is_ok());
+    assert!(interpret(&states, tokens!["N", "<", "N", "<", "N"]).is_err());
+}
+
+#[test]
+fn missing_assoc_is_still_a_conflict() {
+    let _tls = Tls::test();
+    let grammar = normalized_grammar(r#"
+grammar;
+    extern { enum Tok { "+" => .., "N" => .. } }
+
+    S: () = E;
+
+    E: () = {
+        #[precedence(level="0")]
+        "N",
+
+        #[precedence(level="1")]
+        E "+" E,
+    };
+"#);
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+
+    let err = build_lr1_states(&grammar, nt("S")).unwrap_err();
+    assert_eq!(err.conflicts.len(), 1);
+}
+
+#[test]
+fn unannotated_is_still_a_conflict() {
+    let _tls = Tls::test();
+    let grammar = normalized_grammar(r#"
+grammar;
+    extern { enum Tok { "+" => .., "*" => .., "N" => .. } }
+
+    S: () = E;
+
+    E: () = {
+        "N",
+
+        #[precedence(level="1")] #[assoc(side="left")]
+        E "*" E,
+
+        E "+" E,
+    };
+"#);
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+
+    assert!(build_lr1_states(&grammar, nt("S")).is_err());
+}
+
+#[test]
+fn unannotated_is_still_a_conflict_lane_table() {
+    let _tls = Tls::test();
+    let grammar = normalized_grammar(r#"
+grammar;
+    extern { enum Tok { "+" => .., "N" => .. } }
+
+    S: () = E;
+
+    E: () = {
+        "N",
+        E "+" E,
+    };
+"#);
+    let _lr1_tls = Lr1Tls::install(grammar.terminals.clone());
+
+    let err = build_lane_table_states(&grammar, nt("S")).unwrap_err();
+    assert_eq!(err.conflicts.len(), 1);
+}
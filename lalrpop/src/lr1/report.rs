@@ -0,0 +1,75 @@
+//! Writes a human-readable report of the LR(1) states built for a
+//! start symbol, along with the conflicts found in them. This is the
+//! `.lalrpop.txt` file written when `Configuration::emit_report` is
+//! set, and is meant for debugging grammars.
+
+use grammar::repr::*;
+use lr1::core::*;
+use lr1::error::describe_conflicts;
+use std::io::{self, Write};
+
+pub fn write_report<'grammar, W: Write>(out: &mut W,
+                                        grammar: &'grammar Grammar,
+                                        user_start: NonterminalString,
+                                        result: &LR1Result<'grammar>)
+                                        -> io::Result<()> {
+    let states = match *result {
+        Ok(ref states) => states,
+        Err(ref error) => &error.states,
+    };
+
+    try!(writeln!(out, "Start symbol `{}`: {} states", user_start, states.len()));
+    for state in states {
+        try!(writeln!(out, ""));
+        try!(write_state(out, state));
+    }
+
+    try!(writeln!(out, ""));
+    match *result {
+        Ok(_) => try!(writeln!(out, "No conflicts.")),
+        Err(ref error) => {
+            let conflicts = describe_conflicts(grammar, error);
+            try!(writeln!(out, "Conflicts ({}):", conflicts.len()));
+            for (conflict, description) in conflicts {
+                let action = match conflict.action {
+                    Action::Shift(terminal, state) => format!("shift `{}` to {:?}", terminal, state),
+                    Action::Reduce(production) => format!("reduce `{:?}`", production),
+                };
+                try!(writeln!(out,
+                              "    {:?} on {}: reduce `{:?}` or {}",
+                              conflict.state,
+                              conflict.lookahead,
+                              conflict.production,
+                              action));
+                try!(writeln!(out, "        {}", description));
+            }
+        }
+    }
+    try!(writeln!(out, ""));
+    Ok(())
+}
+
+fn write_state<W: Write>(out: &mut W, state: &LR1State) -> io::Result<()> {
+    try!(writeln!(out, "{:?}", state.index));
+    for item in state.items.vec.iter() {
+        try!(writeln!(out, "    {:?}", item));
+    }
+
+    if !state.shifts.is_empty() || !state.reductions.is_empty() {
+        try!(writeln!(out, ""));
+    }
+    for (terminal, next) in &state.shifts {
+        try!(writeln!(out, "    {} -> shift {:?}", terminal, next));
+    }
+    for &(ref tokens, production) in &state.reductions {
+        try!(writeln!(out, "    {:?} -> reduce `{:?}`", tokens, production));
+    }
+
+    if !state.gotos.is_empty() {
+        try!(writeln!(out, ""));
+    }
+    for (nonterminal, next) in &state.gotos {
+        try!(writeln!(out, "    {} -> goto {:?}", nonterminal, next));
+    }
+    Ok(())
+}
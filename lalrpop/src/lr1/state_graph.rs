@@ -2,7 +2,9 @@ use grammar::repr::*;
 use lr1::core::*;
 use lr1::lookahead::Lookahead;
 use petgraph::{EdgeDirection, Graph};
+use petgraph::dot::Dot;
 use petgraph::graph::NodeIndex;
+use util::DotLabel;
 
 // Each state `s` corresponds to the node in the graph with index
 // `s`. The edges are the shift transitions.
@@ -45,6 +47,31 @@ impl StateGraph {
         StateGraph { graph: graph }
     }
 
+    /// Renders the graph in graphviz's `.dot` format. Each state is
+    /// labeled with its kernel items: those with something to the
+    /// left of the cursor, plus the items for `start` in the initial
+    /// state.
+    pub fn to_dot<'grammar, L>(&self,
+                               states: &[State<'grammar, L>],
+                               start: NonterminalString)
+                               -> String
+        where L: Lookahead
+    {
+        let graph = self.graph.map(
+            |node, _| {
+                let state = &states[node.index()];
+                let mut label = format!("{:?}\n", state.index);
+                for item in state.items.vec.iter() {
+                    if item.index > 0 || item.production.nonterminal == start {
+                        label.push_str(&format!("{:?}\n", item));
+                    }
+                }
+                DotLabel(label)
+            },
+            |_, symbol| DotLabel(symbol.to_string()));
+        format!("{}", Dot::new(&graph))
+    }
+
     /// Given a list of symbols `[X, Y, Z]`, traces back from
     /// `initial_state_index` to find the set of states whence we
     /// could have arrived at `initial_state_index` after pushing `X`,
@@ -22,7 +22,8 @@ macro_rules! production {
             nonterminal: nt!($x),
             symbols: syms![$($y),*],
             action: ActionFn::new(0),
-            span: Span(0, 0)
+            span: Span(0, 0),
+            precedence: None,
         }
     }
 }
@@ -3,7 +3,7 @@ extern crate lalrpop;
 extern crate rustc_serialize;
 
 use docopt::Docopt;
-use lalrpop::Configuration;
+use lalrpop::{Configuration, Error};
 use std::env;
 use std::io::{self, Write};
 use std::process;
@@ -41,6 +41,35 @@ fn main1() -> io::Result<()> {
         config.emit_comments(true);
     }
 
+    if args.flag_report {
+        config.emit_report(true);
+    }
+
+    if args.flag_dot {
+        config.emit_dot(true);
+    }
+
+    if args.flag_deny_warnings {
+        config.deny_warnings(true);
+    }
+
+    if args.cmd_interpret {
+        match config.interpret_file(&args.arg_grammar, &args.arg_start, &args.arg_terminals) {
+            Ok(Ok(tree)) => {
+                try!(writeln!(io::stdout(), "{}", tree));
+            }
+            Ok(Err(err)) => {
+                try!(writeln!(stderr, "Error: {}", err));
+                process::exit(1);
+            }
+            Err(err) => {
+                try!(report(&mut stderr, &args.arg_grammar, &err));
+                process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+
     if args.arg_inputs.len() == 0 {
         try!(writeln!(stderr, "Error: no input files specified! Try --help for help."));
         process::exit(1);
@@ -50,8 +79,7 @@ fn main1() -> io::Result<()> {
         match config.process_file(&arg) {
             Ok(()) => { }
             Err(err) => {
-                try!(writeln!(stderr, "Error encountered processing `{}`: {}",
-                              arg, err));
+                try!(report(&mut stderr, &arg, &err));
                 process::exit(1);
             }
         }
@@ -60,25 +88,54 @@ fn main1() -> io::Result<()> {
     Ok(())
 }
 
+/// Prints the error encountered processing `input`. The library does
+/// not print the errors it finds in grammars, so print the full
+/// report of each one here.
+fn report<W: Write>(out: &mut W, input: &str, err: &Error) -> io::Result<()> {
+    if let Error::Grammar(ref diagnostics) = *err {
+        for diagnostic in diagnostics {
+            try!(writeln!(out, "{}", diagnostic.rendered));
+        }
+    }
+    writeln!(out, "Error encountered processing `{}`: {}", input, err)
+}
+
 const USAGE: &'static str = "
-Usage: lalrpop [options] <inputs>...
+Usage: lalrpop [options] interpret <grammar> <start> <terminals>
+       lalrpop [options] <inputs>...
        lalrpop --help
 
+The `interpret` command parses <terminals>, separated by whitespace,
+starting from the nonterminal <start> of the grammar in the file
+<grammar>, and prints the parse tree or where parsing stopped. No code
+is generated. Terminals are written as in the grammar, like '\"(\"' or
+'Num', except that the quotes of a string literal may be left out.
+
 Options:
     -l, --level LEVEL    Set the debug level. (Default: info)
                          Valid values: quiet, info, verbose, debug.
-    -f, --force          Force execution, even if the .lalrpop file is older than the .rs file.
+    -f, --force          Force execution, even if the .lalrpop file has not changed.
     -c, --color          Force colorful output, even if this is not a TTY.
     --comments           Enable comments in the generated code.
+    --report             Write a report of the LR(1) states and conflicts to a .lalrpop.txt file.
+    --dot                Write graphviz .dot files of the LR(1) states and the lexer DFA.
+    --deny-warnings      Report warnings as errors.
 ";
 
 #[derive(Debug, RustcDecodable)]
 struct Args {
+    cmd_interpret: bool,
+    arg_grammar: String,
+    arg_start: String,
+    arg_terminals: String,
     arg_inputs: Vec<String>,
     flag_level: Option<LevelFlag>,
     flag_force: bool,
     flag_color: bool,
     flag_comments: bool,
+    flag_report: bool,
+    flag_dot: bool,
+    flag_deny_warnings: bool,
 }
 
 #[derive(Debug, RustcDecodable)]
@@ -115,4 +172,16 @@ mod test {
             .and_then(|d| d.argv(argv().into_iter()).decode())
             .unwrap();
     }
+
+    #[test]
+    fn test_usage_interpret() {
+        let argv = || vec!["lalrpop", "interpret", "file.lalrpop", "Expr", "( N )"];
+        let args: Args = Docopt::new(USAGE)
+            .and_then(|d| d.argv(argv().into_iter()).decode())
+            .unwrap();
+        assert!(args.cmd_interpret);
+        assert_eq!(args.arg_grammar, "file.lalrpop");
+        assert_eq!(args.arg_start, "Expr");
+        assert_eq!(args.arg_terminals, "( N )");
+    }
 }
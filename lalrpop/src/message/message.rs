@@ -44,6 +44,14 @@ impl Message {
             body: body,
         }
     }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn heading(&self) -> &Content {
+        &*self.heading
+    }
 }
 
 impl Content for Message {
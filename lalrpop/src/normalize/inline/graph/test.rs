@@ -12,7 +12,7 @@ fn test_inline_self_cycle() {
     extern { }
     #[inline] A: () = A;
 "#).unwrap();
-    let grammar = lower_helper(&Session::test(), grammar, true).unwrap();
+    let grammar = lower_helper(&Session::test(), grammar).unwrap();
     assert!(inline_order(&grammar).is_err());
 }
 
@@ -25,7 +25,7 @@ fn test_inline_cycle_3() {
     #[inline] B: () = C;
     #[inline] C: () = A;
 "#).unwrap();
-    let grammar = lower_helper(&Session::test(), grammar, true).unwrap();
+    let grammar = lower_helper(&Session::test(), grammar).unwrap();
     assert!(inline_order(&grammar).is_err());
 }
 
@@ -39,7 +39,7 @@ fn test_inline_order() {
     B: () = C;
     #[inline] C: () = A;
 "#).unwrap();
-    let grammar = lower_helper(&Session::test(), grammar, true).unwrap();
+    let grammar = lower_helper(&Session::test(), grammar).unwrap();
     let a = NonterminalString(intern("A"));
     let c = NonterminalString(intern("C"));
     assert_eq!(inline_order(&grammar).unwrap(), vec![a, c]);
@@ -113,6 +113,7 @@ impl<'a> Inliner<'a> {
                 span: self.into_production.span,
                 symbols: prod_symbols,
                 action: action_fn,
+                precedence: self.into_production.precedence,
             });
         } else {
             let next_symbol = into_symbols[0];
@@ -9,7 +9,7 @@ use super::inline;
 
 fn inlined_grammar(text: &str) -> NormResult<Grammar> {
     let g = parser::parse_grammar(text).unwrap();
-    let g = normalize::lower_helper(&Session::test(), g, true).unwrap();
+    let g = normalize::lower_helper(&Session::test(), g).unwrap();
     inline(g)
 }
 
@@ -0,0 +1,204 @@
+//! Lints for definitions that are valid but are almost certainly not
+//! what the user meant. Unlike the problems found by the other
+//! passes, these are reported as warnings and do not stop the parser
+//! from being generated, unless the grammar says otherwise with
+//! `#[deny(lint="..")]` or the session denies all warnings.
+//!
+//! This runs before inlining, since inlining leaves the nonterminals
+//! it inlines unreferenced.
+
+use collections::{set, Set};
+use grammar::consts::{ALLOW, DENY, LINT};
+use grammar::parse_tree::Span;
+use grammar::repr::*;
+use message::Message;
+use message::builder::{Builder, HeadingCharacter, MessageBuilder, WrapCharacter};
+use session::Session;
+
+#[cfg(test)]
+mod test;
+
+pub struct Warning {
+    /// Whether this should be reported as an error.
+    pub is_error: bool,
+    pub message: Message,
+}
+
+pub fn lint(session: &Session, grammar: &Grammar) -> Vec<Warning> {
+    let reachable = reachable_nonterminals(grammar);
+    let productive = productive_nonterminals(grammar);
+
+    let mut linter = Linter {
+        session: session,
+        grammar: grammar,
+        warnings: vec![],
+    };
+    for (&nonterminal, data) in &grammar.nonterminals {
+        if !is_user_nonterminal(grammar, nonterminal) {
+            continue;
+        }
+        if !reachable.contains(&nonterminal) {
+            linter.warn(Lint::UnreachableNonterminals, data.span, |heading| {
+                heading.text("nonterminal")
+                       .push(nonterminal)
+                       .verbatimed()
+                       .text("is never used")
+            });
+        } else if !productive.contains(&nonterminal) {
+            linter.warn(Lint::UnproductiveNonterminals, data.span, |heading| {
+                heading.text("nonterminal")
+                       .push(nonterminal)
+                       .verbatimed()
+                       .text("can never be parsed")
+            });
+        }
+    }
+
+    // The conversions of an internal tokenizer are made up from the
+    // terminals the grammar uses, so only those of an `extern` token
+    // declaration can go unused.
+    if grammar.intern_token.is_none() {
+        let used: Set<TerminalString> =
+            grammar.nonterminals
+                   .values()
+                   .flat_map(|data| &data.productions)
+                   .flat_map(|production| &production.symbols)
+                   .filter_map(|symbol| match *symbol {
+                       Symbol::Terminal(terminal) => Some(terminal),
+                       Symbol::Nonterminal(_) => None,
+                   })
+                   .collect();
+        for (&terminal, pattern) in &grammar.conversions {
+            if !used.contains(&terminal) {
+                linter.warn(Lint::UnusedTerminals, pattern.span, |heading| {
+                    heading.text("terminal")
+                           .push(terminal)
+                           .verbatimed()
+                           .text("is never used")
+                });
+            }
+        }
+    }
+
+    let mut warnings = linter.warnings;
+    warnings.sort_by_key(|warning| warning.message.span());
+    warnings
+}
+
+struct Linter<'session, 'grammar> {
+    session: &'session Session,
+    grammar: &'grammar Grammar,
+    warnings: Vec<Warning>,
+}
+
+impl<'session, 'grammar> Linter<'session, 'grammar> {
+    /// Reports `lint` at `span`, unless the grammar allows it. The
+    /// `heading` callback adds what was found, after the `warning:`
+    /// or `error:` prefix.
+    fn warn<F>(&mut self, lint: Lint, span: Span, heading: F)
+        where F: FnOnce(Builder<WrapCharacter<HeadingCharacter>>)
+                        -> Builder<WrapCharacter<HeadingCharacter>>
+    {
+        let level = self.grammar.lint_level(lint);
+        if level == LintLevel::Allow {
+            return;
+        }
+        let is_error = level == LintLevel::Deny || self.session.deny_warnings;
+
+        let note = if level == LintLevel::Deny {
+            format!("This is an error because of `#[{}({}=\"{}\")]`.", DENY, LINT, lint.name())
+        } else if is_error {
+            "This is an error because warnings are denied.".to_string()
+        } else {
+            format!("Add `#[{}({}=\"{}\")]` to the grammar to silence this warning.",
+                    ALLOW, LINT, lint.name())
+        };
+
+        let message = MessageBuilder::new(span).heading().begin_wrap();
+        let message = heading(message.text(if is_error { "error:" } else { "warning:" }));
+        let message = message.end()
+                             .end()
+                             .body()
+                             .begin_lines()
+                             .wrap_text(explanation(lint))
+                             .wrap_text(note)
+                             .end()
+                             .end()
+                             .end();
+        self.warnings.push(Warning {
+            is_error: is_error,
+            message: message,
+        });
+    }
+}
+
+fn explanation(lint: Lint) -> &'static str {
+    match lint {
+        Lint::UnreachableNonterminals =>
+            "No public nonterminal refers to it, directly or indirectly, \
+             so it is not part of any parser.",
+        Lint::UnproductiveNonterminals =>
+            "Each of its alternatives refers to itself, or to another \
+             nonterminal that can never be parsed, so no finite input \
+             matches it.",
+        Lint::UnusedTerminals =>
+            "The `extern` token declaration defines it, but no alternative \
+             of the grammar refers to it.",
+    }
+}
+
+/// Whether `nonterminal` was declared by the user, as opposed to being
+/// synthesized for a public symbol, a macro or an expression like
+/// `X*`; the names of those always contain some punctuation, or begin
+/// with the grammar's prefix.
+fn is_user_nonterminal(grammar: &Grammar, nonterminal: NonterminalString) -> bool {
+    let name = nonterminal.to_string();
+    !name.starts_with(&grammar.prefix) && name.chars().all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// The nonterminals that some public nonterminal refers to, directly
+/// or indirectly.
+fn reachable_nonterminals(grammar: &Grammar) -> Set<NonterminalString> {
+    let mut reachable = set();
+    let mut stack: Vec<_> = grammar.start_nonterminals.keys().cloned().collect();
+    while let Some(nonterminal) = stack.pop() {
+        if !reachable.insert(nonterminal) {
+            continue;
+        }
+        for production in grammar.productions_for(nonterminal) {
+            for symbol in &production.symbols {
+                if let Symbol::Nonterminal(nt) = *symbol {
+                    stack.push(nt);
+                }
+            }
+        }
+    }
+    reachable
+}
+
+/// The nonterminals that derive at least one finite string of
+/// terminals: those with an alternative made up of terminals and of
+/// nonterminals that are themselves productive.
+fn productive_nonterminals(grammar: &Grammar) -> Set<NonterminalString> {
+    let mut productive = set();
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for data in grammar.nonterminals.values() {
+            if productive.contains(&data.name) {
+                continue;
+            }
+            let derives_string = data.productions.iter().any(|production| {
+                production.symbols.iter().all(|symbol| match *symbol {
+                    Symbol::Terminal(_) => true,
+                    Symbol::Nonterminal(nt) => productive.contains(&nt),
+                })
+            });
+            if derives_string {
+                productive.insert(data.name);
+                changed = true;
+            }
+        }
+    }
+    productive
+}
@@ -0,0 +1,101 @@
+use message::Content;
+use normalize::lower_helper;
+use parser;
+use session::Session;
+use tls::Tls;
+
+use super::lint;
+
+/// Returns the text each warning points at, along with its heading.
+fn lints(session: &Session, text: &str) -> Vec<(String, String)> {
+    let _tls = Tls::test_string(text);
+    let grammar = parser::parse_grammar(text).unwrap();
+    let grammar = lower_helper(session, grammar).unwrap();
+    lint(session, &grammar)
+        .iter()
+        .map(|warning| {
+            let span = warning.message.span();
+            let heading: Vec<_> = warning.message
+                                         .heading()
+                                         .emit_to_canvas(0)
+                                         .to_strings()
+                                         .iter()
+                                         .map(|row| row.to_string())
+                                         .collect();
+            let heading = heading.join(" ");
+            // the heading is all that differs between a warning and
+            // an error
+            assert_eq!(warning.is_error, heading.starts_with("error:"));
+            (text[span.0..span.1].to_string(), heading.trim().to_string())
+        })
+        .collect()
+}
+
+fn expect(text: &str, expected: Vec<(&str, &str)>) {
+    expect_with_session(&Session::test(), text, expected)
+}
+
+fn expect_with_session(session: &Session, text: &str, expected: Vec<(&str, &str)>) {
+    let actual = lints(session, text);
+    let actual: Vec<_> = actual.iter().map(|&(ref s, ref h)| (&s[..], &h[..])).collect();
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn unreachable() {
+    expect(r#"grammar; pub A: () = "a" A? => (); B = "b" C; C = "c";"#,
+           vec![("B", "warning: nonterminal `B` is never used"),
+                ("C", "warning: nonterminal `C` is never used")]);
+}
+
+#[test]
+fn unproductive() {
+    expect(r#"grammar; pub A = { "a" => (), B }; B: () = { "b" B => (), "(" C ")" => () }; C = B;"#,
+           vec![("B", "warning: nonterminal `B` can never be parsed"),
+                ("C", "warning: nonterminal `C` can never be parsed")]);
+}
+
+#[test]
+fn unused_conversion() {
+    expect(r#"grammar; extern { enum Tok { "a" => Tok::A, "b" => Tok::B } } pub A = "a";"#,
+           vec![("Tok::B", "warning: terminal `\"b\"` is never used")]);
+}
+
+#[test]
+fn no_warnings() {
+    // nonterminals that are inlined, or only used through macros and
+    // repetitions, are still used
+    expect(r#"grammar;
+              pub A = Comma<B> C* D;
+              Comma<T> = (<T> ",")*;
+              B = "b";
+              C = "c";
+              #[inline] D = "d";"#,
+           vec![]);
+}
+
+#[test]
+fn allow() {
+    expect(r#"#[allow(lint="unreachable_nonterminals")]
+              grammar; extern { enum Tok { "a" => Tok::A, "b" => Tok::B } } pub A = "a"; B = "a";"#,
+           vec![("Tok::B", "warning: terminal `\"b\"` is never used")]);
+}
+
+#[test]
+fn deny() {
+    expect(r#"#[deny(lint="unreachable_nonterminals")]
+              grammar; extern { enum Tok { "a" => Tok::A, "b" => Tok::B } } pub A = "a"; B = "a";"#,
+           vec![("Tok::B", "warning: terminal `\"b\"` is never used"),
+                ("B", "error: nonterminal `B` is never used")]);
+}
+
+#[test]
+fn deny_warnings() {
+    let mut session = Session::test();
+    session.deny_warnings = true;
+    expect_with_session(
+        &session,
+        r#"#[allow(lint="unreachable_nonterminals")]
+           grammar; extern { enum Tok { "a" => Tok::A, "b" => Tok::B } } pub A = "a"; B = "a";"#,
+        vec![("Tok::B", "error: terminal `\"b\"` is never used")]);
+}
@@ -7,7 +7,8 @@ use normalize::norm_util::{self, Symbols};
 use grammar::consts::*;
 use grammar::pattern::{Pattern, PatternKind};
 use grammar::parse_tree as pt;
-use grammar::parse_tree::{InternToken, NonterminalString, TerminalString, read_algorithm};
+use grammar::parse_tree::{InternToken, NonterminalString, TerminalString, read_algorithm,
+                          read_lint_levels};
 use grammar::repr as r;
 use session::Session;
 use collections::{map, Map};
@@ -82,11 +83,23 @@ impl<'s> LowerState<'s> {
                                         }
                                         ]),
                                                     };
-                                                    (TerminalString::Literal(literal), pattern)
+                                                    (data.terminal(literal), pattern)
                                                 }));
                     self.intern_token = Some(data);
                 }
 
+                pt::GrammarItem::Import(..) => {
+                    panic!("import not resolved when the grammar was parsed");
+                }
+
+                pt::GrammarItem::MatchToken(..) => {
+                    panic!("match declaration not removed by token check");
+                }
+
+                pt::GrammarItem::Skip(..) => {
+                    panic!("skip declaration not removed by token check");
+                }
+
                 pt::GrammarItem::ExternToken(data) => {
                     if let Some(enum_token) = data.enum_token {
                         token_span = Some(enum_token.type_span);
@@ -119,6 +132,7 @@ impl<'s> LowerState<'s> {
                                                         span: alt.span,
                                                         symbols: symbols,
                                                         action: action,
+                                                        precedence: precedence(&alt.annotations),
                                                     }
                                                 })
                                                 .collect();
@@ -145,8 +159,9 @@ impl<'s> LowerState<'s> {
 
         let mut algorithm = r::Algorithm::default();
 
-        // FIXME Error recovery only works for parse tables so temporarily only generate parse tables for
-        // testing
+        // Actions in grammars that use error recovery typically record
+        // the errors they see through a parameter, so running both
+        // parsers over the same input would record each error twice.
         if self.session.unit_test && !self.uses_error_recovery {
             algorithm.codegen = r::LrCodeGeneration::TestAll;
         }
@@ -178,6 +193,7 @@ impl<'s> LowerState<'s> {
             parameters: parameters,
             where_clauses: grammar.where_clauses,
             algorithm: algorithm,
+            lint_levels: read_lint_levels(&grammar.annotations),
             intern_token: self.intern_token,
             terminals: r::TerminalSet {
                 all: all_terminals,
@@ -214,6 +230,7 @@ impl<'s> LowerState<'s> {
                        symbols: symbols,
                        action: action_fn,
                        span: nt.span,
+                       precedence: None,
                    };
                    self.nonterminals.insert(fake_name,
                                             r::NonterminalData {
@@ -378,6 +395,28 @@ impl<'s> LowerState<'s> {
     }
 }
 
+/// Reads the `#[precedence(level="N")]` and `#[assoc(side="..")]`
+/// annotations of an alternative. These have already been checked by
+/// `prevalidate`.
+fn precedence(annotations: &[pt::Annotation]) -> Option<r::Precedence> {
+    let value = |id: &str| {
+        annotations.iter()
+                   .filter(|a| a.id == intern(id))
+                   .filter_map(|a| a.arg.as_ref())
+                   .map(|arg| arg.value)
+                   .next()
+    };
+
+    value(PRECEDENCE).map(|level| {
+        r::Precedence {
+            level: intern::read(|interner| interner.data(level).parse().unwrap()),
+            assoc: value(ASSOC).map(|side| {
+                intern::read(|interner| r::Assoc::from_str(interner.data(side)).unwrap())
+            }),
+        }
+    })
+}
+
 fn patterns<I>(mut chosen: I, num_args: usize) -> Vec<InternedString>
     where I: Iterator<Item = (usize, InternedString)>
 {
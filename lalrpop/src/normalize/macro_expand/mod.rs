@@ -21,7 +21,9 @@ use std::mem;
 mod test;
 
 pub fn expand_macros(input: Grammar) -> NormResult<Grammar> {
-    let input = try!(resolve::resolve(input));
+    // resolution reports every error it finds, but the grammar has
+    // normally been resolved already, so the first one is enough here
+    let input = try!(resolve::resolve(input).map_err(|mut errors| errors.remove(0)));
 
     let items = input.items;
 
@@ -98,7 +100,10 @@ impl MacroExpander {
         match *item {
             GrammarItem::ExternToken(..) => { }
             GrammarItem::InternToken(..) => { }
+            GrammarItem::MatchToken(..) => { }
+            GrammarItem::Skip(..) => { }
             GrammarItem::Use(..) => { }
+            GrammarItem::Import(..) => { }
             GrammarItem::Nonterminal(ref mut data) => {
                 // Should not encounter macro definitions here,
                 // they've already been siphoned off.
@@ -190,6 +195,7 @@ impl MacroExpander {
             }
             alternatives.push(Alternative {
                 span: span,
+                annotations: alternative.annotations.clone(),
                 expr: self.macro_expand_expr_symbol(&args, &alternative.expr),
                 condition: None,
                 action: alternative.action.clone(),
@@ -369,6 +375,7 @@ impl MacroExpander {
             args: vec![],
             type_decl: Some(ty_ref),
             alternatives: vec![Alternative { span: span,
+                                             annotations: vec![],
                                              expr: expr,
                                              condition: None,
                                              action: action("(<>)") }]
@@ -406,6 +413,7 @@ impl MacroExpander {
                         // X* =
                         Alternative {
                             span: span,
+                            annotations: vec![],
                             expr: ExprSymbol { symbols: vec![] },
                             condition: None,
                             action: action("vec![]")
@@ -414,6 +422,7 @@ impl MacroExpander {
                         // X* = <v:X+>
                         Alternative {
                             span: span,
+                            annotations: vec![],
                             expr: ExprSymbol {
                                 symbols: vec![
                                     Symbol::new(
@@ -445,6 +454,7 @@ impl MacroExpander {
                         // X+ = X
                         Alternative {
                             span: span,
+                            annotations: vec![],
                             expr: ExprSymbol {
                                 symbols: vec![repeat.symbol.clone()]
                             },
@@ -455,6 +465,7 @@ impl MacroExpander {
                         // X+ = <v:X+> <e:X>
                         Alternative {
                             span: span,
+                            annotations: vec![],
                             expr: ExprSymbol {
                                 symbols: vec![
                                     Symbol::new(span, SymbolKind::Name(
@@ -483,6 +494,7 @@ impl MacroExpander {
                     alternatives: vec![
                         // X? = X => Some(<>)
                         Alternative { span: span,
+                                      annotations: vec![],
                                       expr: ExprSymbol {
                                           symbols: vec![repeat.symbol.clone()]
                                       },
@@ -491,6 +503,7 @@ impl MacroExpander {
 
                         // X? = { => None; }
                         Alternative { span: span,
+                                      annotations: vec![],
                                       expr: ExprSymbol {
                                           symbols: vec![]
                                       },
@@ -513,6 +526,7 @@ impl MacroExpander {
             type_decl: None,
             alternatives: vec![
                 Alternative { span: span,
+                              annotations: vec![],
                               expr: ExprSymbol { symbols: vec![] },
                               condition: None,
                               action: Some(action) }]
@@ -536,5 +550,6 @@ fn inline(span: Span) -> Vec<Annotation> {
     vec![Annotation {
                 id_span: span,
                 id: intern(INLINE),
+                arg: None,
     }]
 }
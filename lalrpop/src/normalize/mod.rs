@@ -8,6 +8,8 @@ use grammar::parse_tree as pt;
 use grammar::repr as r;
 use session::Session;
 
+pub use self::lint::Warning;
+
 pub type NormResult<T> = Result<T, NormError>;
 
 #[derive(Clone, Debug)]
@@ -25,35 +27,59 @@ macro_rules! return_err {
     }
 }
 
-pub fn normalize(session: &Session, grammar: pt::Grammar) -> NormResult<r::Grammar> {
-    normalize_helper(session, grammar, true)
+macro_rules! push_err {
+    ($errors: expr, $span: expr, $($args:expr),+) => {
+        $errors.push(NormError {
+            message: format!($($args),+),
+            span: $span
+        });
+    }
 }
 
-/// for unit tests, it is convenient to skip the validation step, and supply a dummy session
+/// Normalizes `grammar`, returning it along with the warnings found
+/// by the lint pass, some of which may have to be reported as errors.
+/// The validation, resolution and token check steps report every
+/// error they find, checking each item of the grammar independently;
+/// the other passes stop at the first error.
+pub fn normalize(session: &Session,
+                 grammar: pt::Grammar)
+                 -> Result<(r::Grammar, Vec<Warning>), Vec<NormError>> {
+    profile!(session, "Grammar validation", try!(prevalidate::validate(&grammar)));
+    normalize_helper(session, grammar)
+}
+
+/// for unit tests, it is convenient to skip the validation and lint
+/// steps, and supply a dummy session
 #[cfg(test)]
 pub fn normalize_without_validating(grammar: pt::Grammar) -> NormResult<r::Grammar> {
-    normalize_helper(&Session::new(), grammar, false)
+    let grammar = try!(lower_helper(&Session::new(), grammar).map_err(|mut errors| errors.remove(0)));
+    inline::inline(grammar)
 }
 
 fn normalize_helper(session: &Session,
-                    grammar: pt::Grammar,
-                    validate: bool)
-                    -> NormResult<r::Grammar> {
-    let grammar = try!(lower_helper(session, grammar, validate));
-    let grammar = profile!(session, "Inlining", try!(inline::inline(grammar)));
-    Ok(grammar)
+                    grammar: pt::Grammar)
+                    -> Result<(r::Grammar, Vec<Warning>), Vec<NormError>> {
+    let grammar = try!(lower_helper(session, grammar));
+    let warnings = profile!(session, "Lint", lint::lint(session, &grammar));
+    let grammar = profile!(session, "Inlining", try!(inline::inline(grammar).map_err(one)));
+    Ok((grammar, warnings))
 }
 
-fn lower_helper(session: &Session, grammar: pt::Grammar, validate: bool) -> NormResult<r::Grammar> {
-    profile!(session, "Grammar validation", if validate { try!(prevalidate::validate(&grammar)); });
+fn lower_helper(session: &Session, grammar: pt::Grammar) -> Result<r::Grammar, Vec<NormError>> {
     let grammar = profile!(session, "Grammar resolution", try!(resolve::resolve(grammar)));
-    let grammar = profile!(session, "Macro expansion", try!(macro_expand::expand_macros(grammar)));
+    let grammar = profile!(session, "Macro expansion",
+                           try!(macro_expand::expand_macros(grammar).map_err(one)));
     let grammar = profile!(session, "Token check", try!(token_check::validate(grammar)));
-    let types = profile!(session, "Infer types", try!(tyinfer::infer_types(&grammar)));
-    let grammar = profile!(session, "Lowering", try!(lower::lower(session, grammar, types)));
+    let types = profile!(session, "Infer types", try!(tyinfer::infer_types(&grammar).map_err(one)));
+    let grammar = profile!(session, "Lowering",
+                           try!(lower::lower(session, grammar, types).map_err(one)));
     Ok(grammar)
 }
 
+fn one(error: NormError) -> Vec<NormError> {
+    vec![error]
+}
+
 // These are executed *IN ORDER*:
 
 // Check most safety conditions.
@@ -91,6 +117,10 @@ mod tyinfer;
 // Lowers the parse tree to the repr notation.
 mod lower;
 
+// Warn about unreachable and unproductive nonterminals and unused
+// `extern` conversions.
+mod lint;
+
 // Inline nonterminals that have requested it.
 mod inline;
 
@@ -6,14 +6,16 @@ use super::norm_util::{self, Symbols};
 use grammar::consts::*;
 use grammar::parse_tree::*;
 use grammar::repr as r;
-use intern::{intern, InternedString};
+use intern::{self, intern, InternedString};
 use collections::{Multimap, set};
 use util::Sep;
 
 #[cfg(test)]
 mod test;
 
-pub fn validate(grammar: &Grammar) -> NormResult<()> {
+/// Checks every item of the grammar, reporting the first error found
+/// in each (and in each alternative of a nonterminal).
+pub fn validate(grammar: &Grammar) -> Result<(), Vec<NormError>> {
     let extern_token: Option<&ExternToken> =
         grammar.items
                .iter()
@@ -25,7 +27,12 @@ pub fn validate(grammar: &Grammar) -> NormResult<()> {
         extern_token: extern_token,
     };
 
-    validator.validate()
+    let errors = validator.validate();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
 }
 
 struct Validator<'grammar> {
@@ -34,72 +41,220 @@ struct Validator<'grammar> {
 }
 
 impl<'grammar> Validator<'grammar> {
-    fn validate(&self) -> NormResult<()> {
+    fn validate(&self) -> Vec<NormError> {
+        let mut errors = vec![];
+        errors.extend(self.validate_grammar_annotations().err());
+        for item in &self.grammar.items {
+            errors.extend(self.validate_item(item).err());
+            if let GrammarItem::Nonterminal(ref data) = *item {
+                for alternative in &data.alternatives {
+                    errors.extend(self.validate_alternative(alternative).err());
+                }
+            }
+        }
+        errors
+    }
+
+    fn validate_grammar_annotations(&self) -> NormResult<()> {
         let allowed_names = vec![intern(LALR),
+                                 intern(LANE_TABLE),
                                  intern(TABLE_DRIVEN),
                                  intern(RECURSIVE_ASCENT),
-                                 intern(TEST_ALL)];
+                                 intern(TEST_ALL),
+                                 intern(RECOVERY),
+                                 intern(RECOVERY_LIMIT),
+                                 intern(ALLOW),
+                                 intern(DENY)];
         for annotation in &self.grammar.annotations {
             if !allowed_names.contains(&annotation.id) {
                 return_err!(annotation.id_span,
                             "unrecognized annotation `{}`",
                             annotation.id);
             }
+            if annotation.id == intern(RECOVERY) {
+                try!(self.validate_recovery(annotation));
+            } else if annotation.id == intern(RECOVERY_LIMIT) {
+                try!(self.validate_recovery_limit(annotation));
+            } else if annotation.id == intern(ALLOW) || annotation.id == intern(DENY) {
+                try!(self.validate_lint(annotation));
+            } else {
+                try!(self.validate_no_arg(annotation));
+            }
+        }
+        let annotations = &self.grammar.annotations;
+        let lalr = annotations.iter().any(|a| a.id == intern(LALR));
+        if let Some(lane_table) = annotations.iter().find(|a| a.id == intern(LANE_TABLE)) {
+            if lalr {
+                return_err!(lane_table.id_span,
+                            "cannot use both `{}` and `{}` on the same grammar",
+                            LALR,
+                            LANE_TABLE);
+            }
         }
+        Ok(())
+    }
 
-        for item in &self.grammar.items {
-            match *item {
-                GrammarItem::Use(..) => { }
-                GrammarItem::ExternToken(ref data) => {
-                    if data.span != self.extern_token.unwrap().span {
-                        return_err!(
-                            data.span,
-                            "multiple extern definitions are not permitted");
-                    }
+    /// Checks `#[recovery(strategy="..")]`. Repairing the input is
+    /// only implemented by the table-driven code generator.
+    fn validate_recovery(&self, annotation: &Annotation) -> NormResult<()> {
+        let arg = try!(expect_arg(annotation, STRATEGY));
 
-                    let allowed_names = vec![intern(LOCATION), intern(ERROR)];
-                    let mut new_names = set();
-                    for associated_type in &data.associated_types {
-                        if !allowed_names.contains(&associated_type.type_name) {
-                            return_err!(
-                                associated_type.type_span,
-                                "associated type `{}` not recognized, \
-                                 try one of the following: {}",
-                                associated_type.type_name,
-                                Sep(", ", &allowed_names));
-                        } else if !new_names.insert(associated_type.type_name) {
-                            return_err!(
-                                associated_type.type_span,
-                                "associated type `{}` already specified",
-                                associated_type.type_name);
-                        }
+        let strategy = intern::read(|interner| r::RecoveryStrategy::from_str(interner.data(arg.value)));
+        match strategy {
+            None => {
+                return_err!(arg.name_span,
+                            "recovery strategy `{}` not recognized, \
+                             try one of the following: panic, repair",
+                            arg.value);
+            }
+            Some(r::RecoveryStrategy::Repair) => {
+                try!(self.validate_table_driven("the `repair` recovery strategy"));
+            }
+            Some(r::RecoveryStrategy::Panic) => { }
+        }
+        Ok(())
+    }
+
+    /// Checks `#[recovery_limit(max="N")]`, which is also only
+    /// implemented by the table-driven code generator.
+    fn validate_recovery_limit(&self, annotation: &Annotation) -> NormResult<()> {
+        let arg = try!(expect_arg(annotation, MAX));
+
+        let valid = intern::read(|interner| interner.data(arg.value).parse::<usize>().is_ok());
+        if !valid {
+            return_err!(arg.name_span,
+                        "recovery limit `{}` is not a non-negative integer",
+                        arg.value);
+        }
+        self.validate_table_driven("the `recovery_limit` annotation")
+    }
+
+    /// Checks `#[allow(lint="..")]` and `#[deny(lint="..")]`.
+    fn validate_lint(&self, annotation: &Annotation) -> NormResult<()> {
+        let arg = try!(expect_arg(annotation, LINT));
+
+        let lint = intern::read(|interner| r::Lint::from_str(interner.data(arg.value)));
+        if lint.is_none() {
+            let names: Vec<_> = r::Lint::all().iter().map(|lint| lint.name()).collect();
+            return_err!(arg.name_span,
+                        "lint `{}` not recognized, try one of the following: {}",
+                        arg.value,
+                        Sep(", ", &names));
+        }
+        Ok(())
+    }
+
+    /// Reports an error if the grammar asks for a code generator
+    /// other than the table-driven one, which is the only one that
+    /// supports `feature`.
+    fn validate_table_driven(&self, feature: &str) -> NormResult<()> {
+        let other_codegen = self.grammar
+                                .annotations
+                                .iter()
+                                .find(|a| a.id == intern(RECURSIVE_ASCENT) || a.id == intern(TEST_ALL));
+        if let Some(other_codegen) = other_codegen {
+            return_err!(other_codegen.id_span,
+                        "{} is only supported by table-driven parsers",
+                        feature);
+        }
+        Ok(())
+    }
+
+    fn validate_item(&self, item: &GrammarItem) -> NormResult<()> {
+        match *item {
+            GrammarItem::Use(..) => { }
+            GrammarItem::Import(ref data) => {
+                return_err!(
+                    data.span,
+                    "cannot import `{}`: imports are only supported when \
+                     processing a `.lalrpop` file",
+                    data.path);
+            }
+            GrammarItem::ExternToken(ref data) => {
+                if data.span != self.extern_token.unwrap().span {
+                    return_err!(
+                        data.span,
+                        "multiple extern definitions are not permitted");
+                }
+
+                let allowed_names = vec![intern(LOCATION), intern(ERROR)];
+                let mut new_names = set();
+                for associated_type in &data.associated_types {
+                    if !allowed_names.contains(&associated_type.type_name) {
+                        return_err!(
+                            associated_type.type_span,
+                            "associated type `{}` not recognized, \
+                             try one of the following: {}",
+                            associated_type.type_name,
+                            Sep(", ", &allowed_names));
+                    } else if !new_names.insert(associated_type.type_name) {
+                        return_err!(
+                            associated_type.type_span,
+                            "associated type `{}` already specified",
+                            associated_type.type_name);
                     }
                 }
-                GrammarItem::Nonterminal(ref data) => {
-                    let inline_annotation = intern(INLINE);
-                    let known_annotations = vec![inline_annotation];
-                    let mut found_annotations = set();
-                    for annotation in &data.annotations {
-                        if !known_annotations.contains(&annotation.id) {
-                            return_err!(annotation.id_span,
-                                        "unrecognized annotation `{}`",
-                                        annotation.id);
-                        } else if !found_annotations.insert(annotation.id) {
-                            return_err!(annotation.id_span,
-                                        "duplicate annotation `{}`",
-                                        annotation.id);
-                        } else if annotation.id == inline_annotation && data.public {
-                            return_err!(annotation.id_span,
-                                        "public items cannot be marked #[inline]");
-                        }
+            }
+            GrammarItem::Nonterminal(ref data) => {
+                let inline_annotation = intern(INLINE);
+                let known_annotations = vec![inline_annotation];
+                let mut found_annotations = set();
+                for annotation in &data.annotations {
+                    if !known_annotations.contains(&annotation.id) {
+                        return_err!(annotation.id_span,
+                                    "unrecognized annotation `{}`",
+                                    annotation.id);
+                    } else if !found_annotations.insert(annotation.id) {
+                        return_err!(annotation.id_span,
+                                    "duplicate annotation `{}`",
+                                    annotation.id);
+                    } else if annotation.id == inline_annotation && data.public {
+                        return_err!(annotation.id_span,
+                                    "public items cannot be marked #[inline]");
                     }
+                    try!(self.validate_no_arg(annotation));
+                }
+            }
+            GrammarItem::MatchToken(ref data) => {
+                if data.span != self.grammar.match_token().unwrap().span {
+                    return_err!(
+                        data.span,
+                        "multiple match declarations are not permitted");
+                }
 
-                    for alternative in &data.alternatives {
-                        try!(self.validate_alternative(alternative));
-                    }
+                if let Some(enum_token) = self.grammar.enum_token() {
+                    return_err!(
+                        data.span,
+                        "match declarations are not permitted when the tokens \
+                         are supplied by `enum {}`",
+                        enum_token.type_name);
+                }
+            }
+            GrammarItem::Skip(ref data) => {
+                if data.span != self.grammar.skip().unwrap().span {
+                    return_err!(
+                        data.span,
+                        "multiple skip declarations are not permitted");
+                }
+
+                if let Some(enum_token) = self.grammar.enum_token() {
+                    return_err!(
+                        data.span,
+                        "skip declarations are not permitted when the tokens \
+                         are supplied by `enum {}`",
+                        enum_token.type_name);
                 }
-                GrammarItem::InternToken(..) => { }
             }
+            GrammarItem::InternToken(..) => { }
+        }
+        Ok(())
+    }
+
+    fn validate_no_arg(&self, annotation: &Annotation) -> NormResult<()> {
+        if let Some(ref arg) = annotation.arg {
+            return_err!(arg.name_span,
+                        "annotation `{}` does not take any arguments",
+                        annotation.id);
         }
         Ok(())
     }
@@ -107,6 +262,8 @@ impl<'grammar> Validator<'grammar> {
     fn validate_alternative(&self,
                             alternative: &Alternative)
                             -> NormResult<()> {
+        try!(self.validate_alternative_annotations(alternative));
+
         try!(self.validate_expr(&alternative.expr));
 
         match norm_util::analyze_expr(&alternative.expr) {
@@ -129,6 +286,63 @@ impl<'grammar> Validator<'grammar> {
         Ok(())
     }
 
+    fn validate_alternative_annotations(&self,
+                                        alternative: &Alternative)
+                                        -> NormResult<()> {
+        let precedence = intern(PRECEDENCE);
+        let assoc = intern(ASSOC);
+        let mut found_annotations = set();
+        for annotation in &alternative.annotations {
+            let arg_name = if annotation.id == precedence {
+                LEVEL
+            } else if annotation.id == assoc {
+                SIDE
+            } else {
+                return_err!(annotation.id_span,
+                            "unrecognized annotation `{}`",
+                            annotation.id);
+            };
+
+            if !found_annotations.insert(annotation.id) {
+                return_err!(annotation.id_span,
+                            "duplicate annotation `{}`",
+                            annotation.id);
+            }
+
+            let arg = try!(expect_arg(annotation, arg_name));
+
+            let valid = intern::read(|interner| {
+                let value = interner.data(arg.value);
+                if annotation.id == precedence {
+                    value.parse::<u32>().is_ok()
+                } else {
+                    r::Assoc::from_str(value).is_some()
+                }
+            });
+            if !valid {
+                if annotation.id == precedence {
+                    return_err!(arg.name_span,
+                                "precedence level `{}` is not a non-negative integer",
+                                arg.value);
+                } else {
+                    return_err!(arg.name_span,
+                                "associativity `{}` not recognized, \
+                                 try one of the following: left, right, none",
+                                arg.value);
+                }
+            }
+        }
+
+        if found_annotations.contains(&assoc) && !found_annotations.contains(&precedence) {
+            let annotation = alternative.annotations.iter().find(|a| a.id == assoc).unwrap();
+            return_err!(annotation.id_span,
+                        "`#[{}]` requires a `#[{}]` annotation on the same alternative",
+                        ASSOC, PRECEDENCE);
+        }
+
+        Ok(())
+    }
+
     fn validate_expr(&self,
                      expr: &ExprSymbol)
                      -> NormResult<()> {
@@ -187,11 +401,7 @@ impl<'grammar> Validator<'grammar> {
                 /* see resolve */
             }
             SymbolKind::Error => {
-                let mut algorithm = r::Algorithm::default();
-                read_algorithm(&self.grammar.annotations, &mut algorithm);
-                if algorithm.codegen == r::LrCodeGeneration::RecursiveAscent {
-                    return_err!(symbol.span, "error recovery is not yet supported by recursive ascent parsers");
-                }
+                /* supported by every code generator */
             }
             SymbolKind::Macro(ref msym) => {
                 debug_assert!(msym.args.len() > 0);
@@ -227,3 +437,21 @@ impl<'grammar> Validator<'grammar> {
         Ok(())
     }
 }
+
+/// The argument of `annotation`, which must be called `name`, as in
+/// `#[id(name="..")]`.
+fn expect_arg<'a>(annotation: &'a Annotation, name: &str) -> NormResult<&'a AnnotationArg> {
+    match annotation.arg {
+        Some(ref arg) if arg.name == intern(name) => Ok(arg),
+        Some(ref arg) => {
+            return_err!(arg.name_span,
+                        "expected `{}` argument for annotation `{}`, found `{}`",
+                        name, annotation.id, arg.name);
+        }
+        None => {
+            return_err!(annotation.id_span,
+                        "annotation `{}` requires an argument, like `#[{}({}=\"..\")]`",
+                        annotation.id, annotation.id, name);
+        }
+    }
+}
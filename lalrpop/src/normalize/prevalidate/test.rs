@@ -3,10 +3,30 @@ use test_util;
 
 fn check_err(expected_err: &str, grammar: &str, span: &str) {
     let parsed_grammar = parser::parse_grammar(&grammar).unwrap();
-    let err = super::validate(&parsed_grammar).unwrap_err();
+    let err = super::validate(&parsed_grammar).unwrap_err().remove(0);
     test_util::check_norm_err(expected_err, span, err);
 }
 
+#[test]
+fn multiple_errors() {
+    let grammar = r#"grammar; Term = { <n:"Num"> }; #[foo] Expr = { <m:"Num"> };"#;
+    let parsed_grammar = parser::parse_grammar(&grammar).unwrap();
+    let errors = super::validate(&parsed_grammar).unwrap_err();
+    let messages: Vec<_> = errors.iter().map(|e| &e.message[..]).collect();
+    assert_eq!(messages,
+               vec![r#"named symbols (like `"Num"`) require a custom action"#,
+                    "unrecognized annotation `foo`",
+                    r#"named symbols (like `"Num"`) require a custom action"#]);
+}
+
+#[test]
+fn lalr_and_lane_table() {
+    check_err(
+        r#"cannot use both `LALR` and `lane_table` on the same grammar"#,
+        r#"#[LALR] #[lane_table] grammar;"#,
+        r#"          ~~~~~~~~~~         "#);
+}
+
 #[test]
 fn named_symbols() {
     check_err(
@@ -31,6 +51,38 @@ fn dup_assoc_type() {
         r#"                                            ~~~~~~~~                      "#);
 }
 
+#[test]
+fn multiple_skip() {
+    check_err(
+        r#"multiple skip declarations are not permitted"#,
+        r#"grammar; skip { r"\s+" } skip { "%" }"#,
+        r#"                         ~~~~         "#);
+}
+
+#[test]
+fn multiple_match() {
+    check_err(
+        r#"multiple match declarations are not permitted"#,
+        r#"grammar; match { _ } match { _ }"#,
+        r#"                     ~~~~~      "#);
+}
+
+#[test]
+fn match_with_extern_token() {
+    check_err(
+        r#"match declarations are not permitted when the tokens are supplied by `enum Tok`"#,
+        r#"grammar; extern { enum Tok { } } match { _ }"#,
+        r#"                                 ~~~~~      "#);
+}
+
+#[test]
+fn skip_with_extern_token() {
+    check_err(
+        r#"skip declarations are not permitted when the tokens are supplied by `enum Tok`"#,
+        r#"grammar; extern { enum Tok { } } skip { r"\s+" }"#,
+        r#"                                 ~~~~           "#);
+}
+
 #[test]
 fn lookahead_without_loc_type() {
     check_err(
@@ -70,3 +122,107 @@ fn pub_inline_annotation() {
         r#"grammar; #[inline] pub Term = ();"#,
         r#"           ~~~~~~            "#);
 }
+
+#[test]
+fn unknown_alternative_annotation() {
+    check_err(
+        r#"unrecognized annotation `foo`"#,
+        r#"grammar; Term = { #[foo] "Num" };"#,
+        r#"                    ~~~         "#);
+}
+
+#[test]
+fn precedence_without_level() {
+    check_err(
+        r#"annotation `precedence` requires an argument"#,
+        r#"grammar; Term = { #[precedence] "Num" };"#,
+        r#"                    ~~~~~~~~~~         "#);
+}
+
+#[test]
+fn bad_precedence_level() {
+    check_err(
+        r#"precedence level `high` is not a non-negative integer"#,
+        r#"grammar; Term = { #[precedence(level="high")] "Num" };"#,
+        r#"                               ~~~~~                 "#);
+}
+
+#[test]
+fn bad_assoc_side() {
+    check_err(
+        r#"associativity `up` not recognized"#,
+        r#"grammar; Term = { #[precedence(level="1")] #[assoc(side="up")] "Num" };"#,
+        r#"                                                   ~~~~             "#);
+}
+
+#[test]
+fn assoc_without_precedence() {
+    check_err(
+        r#"`#\[assoc\]` requires a `#\[precedence\]` annotation"#,
+        r#"grammar; Term = { #[assoc(side="left")] "Num" };"#,
+        r#"                    ~~~~~                       "#);
+}
+
+#[test]
+fn inline_with_argument() {
+    check_err(
+        r#"annotation `inline` does not take any arguments"#,
+        r#"grammar; #[inline(level="1")] Term = { "Num" };"#,
+        r#"                  ~~~~~                        "#);
+}
+
+#[test]
+fn bad_recovery_strategy() {
+    check_err(
+        r#"recovery strategy `guess` not recognized"#,
+        r#"#[recovery(strategy="guess")] grammar;"#,
+        r#"           ~~~~~~~~                   "#);
+}
+
+#[test]
+fn recovery_without_strategy() {
+    check_err(
+        r#"annotation `recovery` requires an argument"#,
+        r#"#[recovery] grammar;"#,
+        r#"  ~~~~~~~~          "#);
+}
+
+#[test]
+fn repair_with_recursive_ascent() {
+    check_err(
+        r#"the `repair` recovery strategy is only supported by table-driven parsers"#,
+        r#"#[recursive_ascent] #[recovery(strategy="repair")] grammar;"#,
+        r#"  ~~~~~~~~~~~~~~~~                                         "#);
+}
+
+#[test]
+fn bad_recovery_limit() {
+    check_err(
+        r#"recovery limit `lots` is not a non-negative integer"#,
+        r#"#[recovery_limit(max="lots")] grammar;"#,
+        r#"                 ~~~                  "#);
+}
+
+#[test]
+fn unknown_lint() {
+    check_err(
+        r#"lint `unused` not recognized, try one of the following: unreachable_nonterminals, "#,
+        r#"#[allow(lint="unused")] grammar;"#,
+        r#"        ~~~~            "#);
+}
+
+#[test]
+fn lint_without_argument() {
+    check_err(
+        r#"annotation `deny` requires an argument, like `#\[deny\(lint=".."\)\]`"#,
+        r#"#[deny] grammar;"#,
+        r#"  ~~~~          "#);
+}
+
+#[test]
+fn recovery_limit_with_test_all() {
+    check_err(
+        r#"the `recovery_limit` annotation is only supported by table-driven parsers"#,
+        r#"#[recovery_limit(max="3")] #[test_all] grammar;"#,
+        r#"                             ~~~~~~~~          "#);
+}
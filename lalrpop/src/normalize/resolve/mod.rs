@@ -10,12 +10,19 @@ use collections::{map, Map};
 #[cfg(test)]
 mod test;
 
-pub fn resolve(mut grammar: Grammar) -> NormResult<Grammar> {
-    try!(resolve_in_place(&mut grammar));
-    Ok(grammar)
+/// Resolves the identifiers of every nonterminal, reporting the first
+/// error found in each alternative (and in each duplicate name).
+pub fn resolve(mut grammar: Grammar) -> Result<Grammar, Vec<NormError>> {
+    let errors = resolve_in_place(&mut grammar);
+    if errors.is_empty() {
+        Ok(grammar)
+    } else {
+        Err(errors)
+    }
 }
 
-fn resolve_in_place(grammar: &mut Grammar) -> NormResult<()> {
+fn resolve_in_place(grammar: &mut Grammar) -> Vec<NormError> {
+    let mut errors = vec![];
     let globals = {
         let nonterminal_identifiers =
             grammar.items
@@ -42,17 +49,13 @@ fn resolve_in_place(grammar: &mut Grammar) -> NormResult<()> {
             if let Some(old_def) = identifiers.insert(id, def) {
                 let description = def.description();
                 let old_description = old_def.description();
-                if description == old_description {
-                    return_err!(
-                        span,
-                        "two {}s declared with the name `{}`",
-                        description, id);
+                let message = if description == old_description {
+                    format!("two {}s declared with the name `{}`", description, id)
                 } else {
-                    return_err!(
-                        span,
-                        "{} and {} both declared with the name `{}`",
-                        description, old_description, id);
-                }
+                    format!("{} and {} both declared with the name `{}`",
+                            description, old_description, id)
+                };
+                errors.push(NormError { message: message, span: span });
             }
         }
 
@@ -63,7 +66,8 @@ fn resolve_in_place(grammar: &mut Grammar) -> NormResult<()> {
         globals: globals,
     };
 
-    validator.validate(grammar)
+    validator.validate(grammar, &mut errors);
+    errors
 }
 
 struct Validator {
@@ -95,25 +99,33 @@ impl Def {
 }
 
 impl Validator {
-    fn validate(&self, grammar: &mut Grammar) -> NormResult<()> {
+    fn validate(&self, grammar: &mut Grammar, errors: &mut Vec<NormError>) {
         for item in &mut grammar.items {
             match *item {
                 GrammarItem::Use(..) => { }
+                GrammarItem::Import(..) => {}
                 GrammarItem::InternToken(..) => {}
+                GrammarItem::MatchToken(..) => {}
+                GrammarItem::Skip(..) => {}
                 GrammarItem::ExternToken(..) => {}
                 GrammarItem::Nonterminal(ref mut data) => {
-                    let identifiers = try!(self.validate_macro_args(data.span, &data.args));
+                    let identifiers = match self.validate_macro_args(data.span, &data.args) {
+                        Ok(identifiers) => identifiers,
+                        Err(error) => {
+                            errors.push(error);
+                            continue;
+                        }
+                    };
                     let locals = ScopeChain {
                         previous: Some(&self.globals),
                         identifiers: identifiers,
                     };
                     for alternative in &mut data.alternatives {
-                        try!(self.validate_alternative(&locals, alternative));
+                        errors.extend(self.validate_alternative(&locals, alternative).err());
                     }
                 }
             }
         }
-        Ok(())
     }
 
     fn validate_macro_args(&self,
@@ -19,7 +19,9 @@ fn check_err(expected_err: &str, grammar: &str) {
         Ok(_) => {
             panic!("expected error for grammar");
         }
-        Err(err) => {
+        Err(errors) => {
+            assert_eq!(errors.len(), 1, "expected one error, found {:?}", errors);
+            let err = &errors[0];
             assert_eq!(err.span, Span(start_index, end_index));
             assert!(expected_err.is_match(&err.message),
                     "unexpected error text `{}`, did not match `{}`", err.message, expected_err);
@@ -75,3 +77,16 @@ fn overlapping_terminal_and_nonterminal() {
         "terminal and nonterminal both declared with the name `A`",
         r#"grammar; A = "Foo"; extern { enum Foo { >>>A => Foo::A(..) <<<} }"#);
 }
+
+#[test]
+fn errors_in_several_alternatives() {
+    let grammar = r#"grammar; X = { Y, Z }; A = "Foo"; A = "Bar"; W<T,T> = "foo";"#;
+    let parsed_grammar = parser::parse_grammar(grammar).unwrap();
+    let errors = super::resolve(parsed_grammar).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|err| &err.message[..]).collect();
+    assert_eq!(messages,
+               vec!["two nonterminals declared with the name `A`",
+                    "no definition found for `Y`",
+                    "no definition found for `Z`",
+                    "multiple macro arguments declared with the name `T`"]);
+}
@@ -13,13 +13,15 @@ use lexer::dfa::{self, DFAConstructionError, Precedence};
 use lexer::nfa::NFAConstructionError::*;
 use grammar::consts::*;
 use grammar::parse_tree::*;
-use collections::Set;
+use collections::{set, Set};
 use collections::{map, Map};
 
 #[cfg(test)]
 mod test;
 
-pub fn validate(mut grammar: Grammar) -> NormResult<Grammar> {
+/// Checks the terminals of every alternative and builds the tokenizer,
+/// if any, reporting all the errors found in either step.
+pub fn validate(mut grammar: Grammar) -> Result<Grammar, Vec<NormError>> {
     let (has_enum_token, all_literals) = {
         let opt_enum_token = grammar.enum_token();
         let conversions = opt_enum_token.map(|et| {
@@ -34,7 +36,10 @@ pub fn validate(mut grammar: Grammar) -> NormResult<Grammar> {
             conversions: conversions,
         };
 
-        try!(validator.validate());
+        let errors = validator.validate();
+        if !errors.is_empty() {
+            return Err(errors);
+        }
 
         (opt_enum_token.is_some(), validator.all_literals)
     };
@@ -43,6 +48,10 @@ pub fn validate(mut grammar: Grammar) -> NormResult<Grammar> {
         try!(construct(&mut grammar, all_literals));
     }
 
+    // Any `match` and `skip` declarations have now been folded into
+    // the intern token.
+    grammar.items.retain(|item| item.as_match_token().is_none() && item.as_skip().is_none());
+
     Ok(grammar)
 }
 
@@ -59,20 +68,24 @@ struct Validator<'grammar> {
 }
 
 impl<'grammar> Validator<'grammar> {
-    fn validate(&mut self) -> NormResult<()> {
+    fn validate(&mut self) -> Vec<NormError> {
+        let mut errors = vec![];
         for item in &self.grammar.items {
             match *item {
                 GrammarItem::Use(..) => { }
+                GrammarItem::Import(_) => { }
                 GrammarItem::ExternToken(_) => { }
                 GrammarItem::InternToken(_) => { }
+                GrammarItem::MatchToken(_) => { }
+                GrammarItem::Skip(_) => { }
                 GrammarItem::Nonterminal(ref data) => {
                     for alternative in &data.alternatives {
-                        try!(self.validate_alternative(alternative));
+                        errors.extend(self.validate_alternative(alternative).err());
                     }
                 }
             }
         }
-        Ok(())
+        errors
     }
 
     fn validate_alternative(&mut self, alternative: &Alternative) -> NormResult<()> {
@@ -156,22 +169,148 @@ impl<'grammar> Validator<'grammar> {
 // Construction phase -- if we are constructing a tokenizer, this
 // phase builds up an internal token DFA.
 
-pub fn construct(grammar: &mut Grammar, literals_map: Map<TerminalLiteral, Span>) -> NormResult<()> {
+pub fn construct(grammar: &mut Grammar,
+                 literals_map: Map<TerminalLiteral, Span>)
+                 -> Result<(), Vec<NormError>> {
+    let mut errors = vec![];
+
+    // Assign each literal a tier; literals in higher tiers win over
+    // those in lower ones. The blocks of a `match` declaration form
+    // tiers in order of decreasing priority, with its `_` entry (if
+    // any) standing for the remaining literals of the grammar. In the
+    // absence of a `match`, all literals share a single tier.
+    let mut tiers: Map<TerminalLiteral, (usize, Span)> = map();
+    let mut renames: Map<TerminalLiteral, TerminalString> = map();
+    let mut names = set();
+    let mut catch_all = None;
+    match grammar.match_token() {
+        None => {
+            catch_all = Some(1);
+        }
+        Some(match_token) => {
+            let num_tiers = match_token.contents.len();
+            for (index, contents) in match_token.contents.iter().enumerate() {
+                let tier = num_tiers - index;
+                for item in &contents.items {
+                    let (literal, name) = match *item {
+                        MatchItem::CatchAll(span) => {
+                            if catch_all.is_some() {
+                                push_err!(
+                                    errors,
+                                    span,
+                                    "`_` may only appear once in a match declaration");
+                            }
+                            catch_all = Some(tier);
+                            continue;
+                        }
+                        MatchItem::Unmapped(_, literal) => {
+                            (literal, TerminalString::Literal(literal))
+                        }
+                        MatchItem::Mapped(_, literal, name) => {
+                            renames.insert(literal, name);
+                            (literal, name)
+                        }
+                    };
+                    if tiers.insert(literal, (tier, item.span())).is_some() {
+                        push_err!(
+                            errors,
+                            item.span(),
+                            "terminal `{}` appears more than once in the match declaration",
+                            literal);
+                        continue;
+                    }
+                    if !names.insert(name) {
+                        push_err!(
+                            errors,
+                            item.span(),
+                            "multiple literals are matched as the terminal `{}`",
+                            name);
+                    }
+                }
+            }
+        }
+    }
+
+    for (&literal, &span) in &literals_map {
+        if names.contains(&TerminalString::Literal(literal)) {
+            continue;
+        }
+        if let Some(&name) = renames.get(&literal) {
+            push_err!(
+                errors,
+                span,
+                "terminal `{}` is matched as `{}`, and must be referred to by that name",
+                literal, name);
+            continue;
+        }
+        match catch_all {
+            Some(tier) => {
+                tiers.insert(literal, (tier, span));
+            }
+            None => {
+                push_err!(
+                    errors,
+                    span,
+                    "terminal `{}` does not appear in the match declaration, \
+                     which has no `_` entry",
+                    literal);
+            }
+        }
+    }
+
+    // Within a tier, quoted literals win over regular expressions.
+    let precedence = |tier: usize, literal: TerminalLiteral| {
+        Precedence(tier * 2 + literal.precedence())
+    };
+
     let mut literals: Vec<TerminalLiteral> =
-        literals_map.keys()
-                    .cloned()
-                    .collect();
+        tiers.keys()
+             .cloned()
+             .collect();
 
     // Sort literals by order of increasing precedence.
-    literals.sort_by_key(|literal| literal.precedence());
-
-    // Build up two vectors, one of parsed regular expressions and
-    // one of precedences, that are parallel with `literals`.
-    let mut regexs = Vec::with_capacity(literals.len());
-    let mut precedences = Vec::with_capacity(literals.len());
-    try!(intern::read(|interner| {
-        for &literal in &literals {
-            precedences.push(Precedence(literal.precedence()));
+    literals.sort_by_key(|literal| precedence(tiers[literal].0, *literal));
+
+    // Literals from a `skip` declaration come after those of the
+    // grammar, so that the generated tokenizer can recognize (and
+    // discard) them by their index. They belong to the lowest tier.
+    let skip: Option<Vec<(Span, TerminalLiteral)>> =
+        grammar.skip().map(|skip| skip.literals.clone());
+    let mut spans: Vec<Span> =
+        literals.iter()
+                .map(|literal| tiers[literal].1)
+                .collect();
+    let mut precedences: Vec<Precedence> =
+        literals.iter()
+                .map(|literal| precedence(tiers[literal].0, *literal))
+                .collect();
+    let mut all_literals = literals.clone();
+    if let Some(ref skip) = skip {
+        let mut skipped = set();
+        for &(span, literal) in skip {
+            if tiers.contains_key(&literal) {
+                push_err!(
+                    errors,
+                    span,
+                    "terminal `{}` is used in the grammar and so cannot be skipped",
+                    literal);
+                continue;
+            }
+            if !skipped.insert(literal) {
+                push_err!(errors, span, "terminal `{}` is skipped more than once", literal);
+                continue;
+            }
+            all_literals.push(literal);
+            spans.push(span);
+            precedences.push(precedence(1, literal));
+        }
+    }
+
+    // Build up a vector of parsed regular expressions that is
+    // parallel with `all_literals`.
+    let mut regexs = Vec::with_capacity(all_literals.len());
+    intern::read(|interner| {
+        for (&literal, &literal_span) in all_literals.iter().zip(&spans) {
             match literal {
                 TerminalLiteral::Quoted(s) => {
                     regexs.push(re::parse_literal(interner.data(s)));
@@ -180,11 +319,11 @@ pub fn construct(grammar: &mut Grammar, literals_map: Map<TerminalLiteral, Span>
                     match re::parse_regex(interner.data(s)) {
                         Ok(regex) => regexs.push(regex),
                         Err(error) => {
-                            let literal_span = literals_map[&literal];
                             // FIXME -- take offset into account for
                             // span; this requires knowing how many #
                             // the user used, which we do not track
-                            return_err!(
+                            push_err!(
+                                errors,
                                 literal_span,
                                 "invalid regular expression: {}",
                                 error);
@@ -193,8 +332,12 @@ pub fn construct(grammar: &mut Grammar, literals_map: Map<TerminalLiteral, Span>
                 }
             }
         }
-        Ok(())
-    }));
+    });
+
+    // the DFA can only be built from a consistent set of literals
+    if !errors.is_empty() {
+        return Err(errors);
+    }
 
     let dfa = match dfa::build_dfa(&regexs, &precedences) {
         Ok(dfa) => dfa,
@@ -206,28 +349,33 @@ pub fn construct(grammar: &mut Grammar, literals_map: Map<TerminalLiteral, Span>
                 LineBoundary => r#"line boundaries (`^` or `$`)"#,
                 TextBoundary => r#"text boundaries (`^` or `$`)"#,
             };
-            let literal = literals[index.index()];
-            let span = literals_map[&literal];
-            return_err!(
+            let span = spans[index.index()];
+            push_err!(
+                errors,
                 span,
                 "{} are not supported in regular expressions",
-                feature)
+                feature);
+            return Err(errors);
         }
         Err(DFAConstructionError::Ambiguity { match0, match1 }) => {
-            let literal0 = literals[match0.index()];
-            let literal1 = literals[match1.index()];
-            let span0 = literals_map[&literal0];
-            let _span1 = literals_map[&literal1];
+            let literal0 = all_literals[match0.index()];
+            let literal1 = all_literals[match1.index()];
+            let span0 = spans[match0.index()];
+            let _span1 = spans[match1.index()];
             // FIXME(#88) -- it'd be nice to give an example here
-            return_err!(
+            push_err!(
+                errors,
                 span0,
                 "ambiguity detected between the terminal `{}` and the terminal `{}`",
                 literal0, literal1);
+            return Err(errors);
         }
     };
 
     grammar.items.push(GrammarItem::InternToken(InternToken {
         literals: literals,
+        renames: renames,
+        skip: skip.map(|skip| skip.into_iter().map(|(_, literal)| literal).collect()),
         dfa: dfa
     }));
 
@@ -237,7 +385,8 @@ pub fn construct(grammar: &mut Grammar, literals_map: Map<TerminalLiteral, Span>
     for parameter in &grammar.type_parameters {
         match *parameter {
             TypeParameter::Lifetime(i) if i == input_lifetime => {
-                return_err!(
+                push_err!(
+                    errors,
                     grammar.span,
                     "since there is no external token enum specified, \
                      the `'input` lifetime is implicit and cannot be declared");
@@ -249,7 +398,8 @@ pub fn construct(grammar: &mut Grammar, literals_map: Map<TerminalLiteral, Span>
     let input_parameter = intern(INPUT_PARAMETER);
     for parameter in &grammar.parameters {
         if parameter.name == input_parameter {
-            return_err!(
+            push_err!(
+                errors,
                 grammar.span,
                 "since there is no external token enum specified, \
                  the `input` parameter is implicit and cannot be declared");
@@ -268,7 +418,11 @@ pub fn construct(grammar: &mut Grammar, literals_map: Map<TerminalLiteral, Span>
     };
     grammar.parameters.push(parameter);
 
-    Ok(())
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
 }
 
 
@@ -8,8 +8,8 @@ fn check_err(expected_err: &str,
              span: &str) {
     let parsed_grammar = parser::parse_grammar(&grammar).unwrap();
     let parsed_grammar = resolve(parsed_grammar).unwrap();
-    let err = super::validate(parsed_grammar).unwrap_err();
-    test_util::check_norm_err(expected_err, span, err);
+    let mut errors = super::validate(parsed_grammar).unwrap_err();
+    test_util::check_norm_err(expected_err, span, errors.remove(0));
 }
 
 fn check_intern_token(grammar: &str,
@@ -19,11 +19,15 @@ fn check_intern_token(grammar: &str,
     let parsed_grammar = resolve(parsed_grammar).unwrap();
     let parsed_grammar = super::validate(parsed_grammar).unwrap();
     let intern_token = parsed_grammar.intern_token().unwrap();
+    let literals: Vec<_> =
+        intern_token.literals.iter()
+                             .chain(intern_token.skip.iter().flat_map(|skip| skip))
+                             .collect();
     for (input, expected_literal) in expected_tokens {
         let actual_literal =
             interpret::interpret(&intern_token.dfa, input)
             .map(|(index, text)| {
-                let literal = intern_token.literals[index.index()];
+                let literal = literals[index.index()];
                 (literal, text)
             });
         let actual_literal = format!("{:?}", actual_literal);
@@ -99,3 +103,121 @@ fn regex_literals() {
             ("9123456", r##"Some((r#"[0-9]+"#, "9123456"))"##),
                 ]);
 }
+
+#[test]
+fn skip_literals() {
+    check_intern_token(
+        r#"grammar; skip { r" +", "%" } X = X "+";"#,
+        vec![
+            ("+", r#"Some(("+", "+"))"#),
+            ("  ", r##"Some((r#" +"#, "  "))"##),
+            ("%", r#"Some(("%", "%"))"#),
+                ]);
+}
+
+#[test]
+fn skip_literal_used_in_grammar() {
+    check_err(
+        r#"terminal `"\+"` is used in the grammar and so cannot be skipped"#,
+        r#"grammar; skip { "+" } X = X "+";"#,
+        r#"                ~~~              "#);
+}
+
+#[test]
+fn skip_literal_twice() {
+    check_err(
+        r#"terminal `"\+"` is skipped more than once"#,
+        r#"grammar; skip { "+", "+" } X = X "-";"#,
+        r#"                     ~~~              "#);
+}
+
+#[test]
+fn skip_literal_ambiguity() {
+    check_err(
+        r#"ambiguity detected between the terminal `.*` and the terminal `.*`"#,
+        r#"grammar; skip { r"[a-c]+" } X = X r"[a-z]+";"#,
+        r#"                ~~~~~~~~~                   "#);
+}
+
+#[test]
+fn match_tiers() {
+    check_intern_token(
+        r#"grammar; match { r"[a-c]+" } else { r"[a-z]+", _ } X = X r"[a-c]+" r"[a-z]+" "+";"#,
+        vec![
+            ("ab", r##"Some((r#"[a-c]+"#, "ab"))"##),
+            ("abd", r##"Some((r#"[a-z]+"#, "abd"))"##),
+            ("+", r#"Some(("+", "+"))"#),
+                ]);
+}
+
+#[test]
+fn match_renamed() {
+    check_intern_token(
+        r#"grammar; match { r"[a-z]+" => "ID" } X = X "ID";"#,
+        vec![
+            ("id", r##"Some((r#"[a-z]+"#, "id"))"##),
+                ]);
+}
+
+#[test]
+fn match_missing_literal() {
+    check_err(
+        r#"terminal `"\+"` does not appear in the match declaration, which has no `_` entry"#,
+        r#"grammar; match { r"[a-z]+" } X = X "+";"#,
+        r#"                                   ~~~ "#);
+}
+
+#[test]
+fn match_renamed_literal_used() {
+    check_err(
+        r#"terminal `"\+"` is matched as `"PLUS"`, and must be referred to by that name"#,
+        r#"grammar; match { "+" => "PLUS" } X = X "+";"#,
+        r#"                                       ~~~ "#);
+}
+
+#[test]
+fn match_literal_twice() {
+    check_err(
+        r#"terminal `"\+"` appears more than once in the match declaration"#,
+        r#"grammar; match { "+" } else { "+" } X = X "-";"#,
+        r#"                              ~~~              "#);
+}
+
+#[test]
+fn match_name_twice() {
+    check_err(
+        r#"multiple literals are matched as the terminal `"\+"`"#,
+        r#"grammar; match { "+", "plus" => "+" } X = X "-";"#,
+        r#"                      ~~~~~~                     "#);
+}
+
+#[test]
+fn match_catch_all_twice() {
+    check_err(
+        r#"`_` may only appear once in a match declaration"#,
+        r#"grammar; match { _ } else { _ } X = X "-";"#,
+        r#"                            ~             "#);
+}
+
+#[test]
+fn errors_in_several_alternatives() {
+    let grammar = r#"grammar; extern { enum Term { } } X = { X "+", X "-" };"#;
+    let parsed_grammar = resolve(parser::parse_grammar(grammar).unwrap()).unwrap();
+    let errors = super::validate(parsed_grammar).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|err| &err.message[..]).collect();
+    assert_eq!(messages,
+               vec![r#"terminal `"+"` does not have a pattern defined for it"#,
+                    r#"terminal `"-"` does not have a pattern defined for it"#]);
+}
+
+#[test]
+fn errors_in_match_and_skip() {
+    let grammar = r#"grammar; match { "+" } skip { "+", "-", "-" } X = X "+" "*";"#;
+    let parsed_grammar = resolve(parser::parse_grammar(grammar).unwrap()).unwrap();
+    let errors = super::validate(parsed_grammar).unwrap_err();
+    let messages: Vec<&str> = errors.iter().map(|err| &err.message[..]).collect();
+    assert_eq!(messages,
+               vec![r#"terminal `"*"` does not appear in the match declaration, which has no `_` entry"#,
+                    r#"terminal `"+"` is used in the grammar and so cannot be skipped"#,
+                    r#"terminal `"-"` is skipped more than once"#]);
+}
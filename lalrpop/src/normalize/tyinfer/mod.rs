@@ -9,7 +9,7 @@ use grammar::parse_tree::{ActionKind, Alternative,
                           Path,
                           Span,
                           SymbolKind,
-                          TerminalString, TypeRef};
+                          TypeRef};
 use grammar::repr::{NominalTypeRepr, Types, TypeRepr};
 use intern::intern;
 
@@ -80,7 +80,7 @@ impl<'grammar> TypeInferencer<'grammar> {
             let mut types = Types::new(&grammar.prefix, Some(loc_type), error_type, enum_type);
 
             for &literal in &intern_token.literals {
-                types.add_term_type(TerminalString::Literal(literal), input_str.clone());
+                types.add_term_type(intern_token.terminal(literal), input_str.clone());
             }
 
             types
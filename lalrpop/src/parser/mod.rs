@@ -8,10 +8,19 @@ mod lrgrammar;
 
 pub type ParseError<'input> = lalrpop_util::ParseError<usize, tok::Tok<'input>, tok::Error>;
 
+#[cfg(test)]
 pub fn parse_grammar<'input>(input: &'input str)
                              -> Result<Grammar, ParseError<'input>>
 {
-    let tokenizer = tok::Tokenizer::new(input, 0);
+    parse_grammar_at(input, 0)
+}
+
+/// Parses a grammar whose text starts at position `offset`, as do the
+/// files imported by another grammar.
+pub fn parse_grammar_at<'input>(input: &'input str, offset: usize)
+                                -> Result<Grammar, ParseError<'input>>
+{
+    let tokenizer = tok::Tokenizer::new(input, offset);
     let mut grammar = try!(lrgrammar::parse_Grammar(input, tokenizer));
 
     // find a unique prefix that does not appear anywhere in the input
@@ -29,6 +38,97 @@ fn parse_pattern<'input>(input: &'input str, offset: usize)
     lrgrammar::parse_Pattern(input, tokenizer)
 }
 
+fn parse_match_mapping<'input>(input: &'input str, offset: usize)
+                               -> Result<TerminalString, ParseError<'input>>
+{
+    let tokenizer = tok::Tokenizer::new(input, offset);
+    lrgrammar::parse_MatchMapping(input, tokenizer)
+}
+
+/// Adds the block `word { block }`, whose `word` is found between `lo`
+/// and `hi`, to the grammar items parsed so far. `match`, `else` and
+/// `skip` blocks all look alike to the parser, so only `word` tells
+/// them apart; that way these keywords remain ordinary identifiers
+/// everywhere else.
+fn push_block<'input>(mut items: Vec<GrammarItem>,
+                      lo: usize,
+                      word: &'input str,
+                      hi: usize,
+                      block: Vec<(MatchItem, Option<(usize, &'input str)>)>)
+                      -> Result<Vec<GrammarItem>, ParseError<'input>>
+{
+    let after_match = match items.last() {
+        Some(&GrammarItem::MatchToken(..)) => true,
+        _ => false,
+    };
+
+    match word {
+        "match" => {
+            items.push(GrammarItem::MatchToken(MatchToken {
+                span: Span(lo, hi),
+                contents: vec![match_contents(block)],
+            }));
+        }
+
+        "else" if after_match => {
+            if let Some(&mut GrammarItem::MatchToken(ref mut m)) = items.last_mut() {
+                m.contents.push(match_contents(block));
+            }
+        }
+
+        "skip" => {
+            let mut literals = vec![];
+            for (item, arrow) in block {
+                match (item, arrow) {
+                    (MatchItem::Unmapped(span, literal), _) => {
+                        literals.push((span, literal));
+                    }
+                    (_, Some((start, code))) => {
+                        let end = start + 2 + code.len();
+                        return Err(unexpected(start, tok::Tok::EqualsGreaterThanCode(code), end,
+                                              &[",", "}"]));
+                    }
+                    (item, None) => {
+                        let span = item.span();
+                        return Err(unexpected(span.0, tok::Tok::Underscore, span.1,
+                                              &["RegexLiteral", "StringLiteral"]));
+                    }
+                }
+            }
+            items.push(GrammarItem::Skip(Skip {
+                span: Span(lo, hi),
+                literals: literals,
+            }));
+        }
+
+        _ => {
+            let keywords: &[&str] = if after_match {
+                &["else", "match", "skip"]
+            } else {
+                &["match", "skip"]
+            };
+            return Err(unexpected(lo, tok::Tok::Id(word), hi, keywords));
+        }
+    }
+
+    Ok(items)
+}
+
+fn match_contents(block: Vec<(MatchItem, Option<(usize, &str)>)>) -> MatchContents {
+    MatchContents { items: block.into_iter().map(|(item, _)| item).collect() }
+}
+
+/// An error for the unexpected token `tok` between `lo` and `hi`,
+/// where one of the tokens named in `expected` was expected.
+fn unexpected<'input>(lo: usize, tok: tok::Tok<'input>, hi: usize, expected: &[&str])
+                      -> ParseError<'input>
+{
+    lalrpop_util::ParseError::UnrecognizedToken {
+        token: Some((lo, tok, hi)),
+        expected: expected.iter().map(|name| format!("{:?}", name)).collect(),
+    }
+}
+
 #[cfg(test)]
 pub fn parse_type_ref<'input>(input: &'input str)
                               -> Result<TypeRef, ParseError<'input>>
@@ -40,6 +40,14 @@ pub struct Session {
     /// forth.
     pub emit_comments: bool,
 
+    /// Write a report of the LR(1) states and conflicts next to each
+    /// generated file.
+    pub emit_report: bool,
+
+    /// Write graphviz files of the LR(1) states and of the lexer DFA
+    /// next to each generated file.
+    pub emit_dot: bool,
+
     pub color_config: ColorConfig,
 
     /// Stop after you find `max_errors` errors. If this value is 0,
@@ -76,6 +84,14 @@ pub struct Session {
 
     /// Unit testing (lalrpop-test) configuration
     pub unit_test: bool,
+
+    /// How many `.lalrpop` files to process at the same time when
+    /// processing a directory.
+    pub jobs: usize,
+
+    /// Report warnings as errors, which stop the parser from being
+    /// generated.
+    pub deny_warnings: bool,
 }
 
 impl Session {
@@ -86,6 +102,8 @@ impl Session {
             out_dir: None,
             force_build: false,
             emit_comments: false,
+            emit_report: false,
+            emit_dot: false,
             color_config: ColorConfig::default(),
             max_errors: 1,
             heading: style::FG_WHITE.with(style::BOLD),
@@ -97,6 +115,8 @@ impl Session {
             nonterminal_symbol: style::DEFAULT,
             hint_text: style::FG_BRIGHT_MAGENTA.with(style::BOLD),
             unit_test: false,
+            jobs: 1,
+            deny_warnings: false,
         }
     }
 
@@ -109,6 +129,8 @@ impl Session {
             out_dir: None,
             force_build: false,
             emit_comments: false,
+            emit_report: false,
+            emit_dot: false,
             color_config: ColorConfig::IfTty,
             max_errors: 1,
             heading: Style::new(),
@@ -120,6 +142,8 @@ impl Session {
             nonterminal_symbol: Style::new(),
             hint_text: Style::new(),
             unit_test: true,
+            jobs: 1,
+            deny_warnings: false,
         }
     }
 
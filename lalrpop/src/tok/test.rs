@@ -392,3 +392,21 @@ fn regex2() {
     ]);
 }
 
+#[test]
+fn contextual_keywords_as_ids() {
+    test(r#"<skip:match> else"#, vec![
+        (r#"~                "#, LessThan),
+        (r#" ~~~~            "#, Id("skip")),
+        (r#"     ~           "#, Colon),
+        (r#"      ~~~~~      "#, Id("match")),
+        (r#"           ~     "#, GreaterThan),
+        (r#"             ~~~~"#, Id("else")),
+    ]);
+    test(r#"X = import "x";"#, vec![
+        (r#"~              "#, Id("X")),
+        (r#"  ~            "#, Equals),
+        (r#"    ~~~~~~     "#, Id("import")),
+        (r#"           ~~~ "#, StringLiteral("x")),
+        (r#"              ~"#, Semi),
+    ]);
+}
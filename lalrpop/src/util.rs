@@ -33,6 +33,16 @@ impl<S:Display> Display for Escape<S> {
     }
 }
 
+/// A node or edge label for `petgraph::dot::Dot`, which escapes quotes
+/// and newlines (left-justifying each line) but not backslashes.
+pub struct DotLabel<S>(pub S);
+
+impl<S:Display> Display for DotLabel<S> {
+    fn fmt(&self, fmt: &mut Formatter) -> Result<(), Error> {
+        write!(fmt, "{}", format!("{}", self.0).replace("\\", "\\\\"))
+    }
+}
+
 pub struct Prefix<S>(pub &'static str, pub S);
 
 impl<'a,S:Display> Display for Prefix<&'a [S]> {